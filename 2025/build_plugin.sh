@@ -0,0 +1,16 @@
+#!/bin/bash
+# Builds one day as a shared library for `--plugins`, e.g. ./build_plugin.sh 7
+
+if [ -z "$1" ]; then
+    echo "Usage: $0 <day>"
+    exit 1
+fi
+
+path_of_script=$(dirname "$0")
+day=$(printf "%02d" "$((10#$1))")
+out_dir="$path_of_script/target/plugins"
+mkdir -p "$out_dir"
+
+rustc --edition 2021 --crate-type cdylib -C opt-level=3 \
+    --crate-name "day$day" --cfg "plugin_day=\"$day\"" \
+    -o "$out_dir/libday$day.so" "$path_of_script/src/plugin_root.rs"
@@ -0,0 +1,161 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+/// How a benchmark is run
+pub struct BenchConfig {
+    /// Measured iterations per day
+    pub iterations: usize,
+
+    /// Iterations run and thrown away first, to warm caches and the allocator
+    pub warmup: usize,
+
+    /// CPU to pin the process to, so the scheduler does not move it mid-run
+    pub cpu: Option<usize>,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        BenchConfig {
+            iterations: 20,
+            warmup: 3,
+            cpu: None,
+        }
+    }
+}
+
+/// Pins this process to a single CPU with `sched_setaffinity`
+pub fn pin_to_cpu(cpu: usize) -> Result<(), String> {
+    if cpu >= libc::CPU_SETSIZE as usize {
+        return Err(format!("CPU {} is out of range", cpu));
+    }
+
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    unsafe { libc::CPU_SET(cpu, &mut set) };
+
+    let result =
+        unsafe { libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) };
+    if result != 0 {
+        return Err(format!(
+            "Failed to pin to CPU {}: {}",
+            cpu,
+            std::io::Error::last_os_error()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Two-sided 95% critical values of Student's t distribution, by degrees of freedom
+const T_CRITICAL_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+
+/// Above 30 degrees of freedom the t distribution is close enough to the normal
+const Z_CRITICAL_95: f64 = 1.960;
+
+/// Timing statistics of one phase over all iterations, after outlier rejection
+pub struct Stats {
+    pub samples: usize,
+
+    /// Samples outside 1.5 interquartile ranges of the middle half, left out of the rest
+    pub outliers: usize,
+
+    pub mean: Duration,
+    pub median: Duration,
+    pub std_dev: Duration,
+
+    /// 95% confidence interval of the mean
+    pub ci_low: Duration,
+    pub ci_high: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        let mut sorted: Vec<f64> = samples.iter().map(|sample| sample.as_secs_f64()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+        let fence = 1.5 * (q3 - q1);
+        let kept: Vec<f64> = sorted
+            .iter()
+            .copied()
+            .filter(|&sample| sample >= q1 - fence && sample <= q3 + fence)
+            .collect();
+
+        let n = kept.len() as f64;
+        let mean = kept.iter().sum::<f64>() / n;
+        let variance = if kept.len() > 1 {
+            kept.iter()
+                .map(|sample| (sample - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1.0)
+        } else {
+            0.0
+        };
+        let std_dev = variance.sqrt();
+
+        let t = match kept.len() {
+            0 | 1 => 0.0,
+            len if len - 1 <= T_CRITICAL_95.len() => T_CRITICAL_95[len - 2],
+            _ => Z_CRITICAL_95,
+        };
+        let margin = t * std_dev / n.sqrt();
+
+        Stats {
+            samples: samples.len(),
+            outliers: samples.len() - kept.len(),
+            mean: Duration::from_secs_f64(mean),
+            median: Duration::from_secs_f64(quantile(&kept, 0.5)),
+            std_dev: Duration::from_secs_f64(std_dev),
+            ci_low: Duration::from_secs_f64((mean - margin).max(0.0)),
+            ci_high: Duration::from_secs_f64(mean + margin),
+        }
+    }
+}
+
+/// Linearly interpolated quantile of sorted, non-empty samples
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+/// Formats a duration in the largest unit that keeps it at one or more
+pub fn format_duration(duration: Duration) -> String {
+    let nanos = duration.as_nanos();
+    if nanos < 1_000 {
+        format!("{}ns", nanos)
+    } else if nanos < 1_000_000 {
+        format!("{:.3}us", nanos as f64 / 1e3)
+    } else if nanos < 1_000_000_000 {
+        format!("{:.3}ms", nanos as f64 / 1e6)
+    } else {
+        format!("{:.3}s", nanos as f64 / 1e9)
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mean {} (95% CI {} to {}), median {}, std dev {}, {} of {} samples dropped as outliers",
+            format_duration(self.mean),
+            format_duration(self.ci_low),
+            format_duration(self.ci_high),
+            format_duration(self.median),
+            format_duration(self.std_dev),
+            self.outliers,
+            self.samples
+        )
+    }
+}
+
+/// Benchmark of one day, with each phase measured on a fresh solution every iteration
+pub struct BenchResult {
+    pub day: u8,
+    pub init: Stats,
+    pub part1: Stats,
+    pub part2: Stats,
+}
@@ -0,0 +1,69 @@
+use std::{collections::HashMap, fmt::Display, str::FromStr};
+
+/// Command line arguments, a command followed by `--flag value` pairs
+///
+/// A flag with no value after it (e.g. `--wrap`) is a switch, see `has`
+pub struct Args {
+    pub command: Option<String>,
+    flags: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    pub fn from_env() -> Args {
+        Args::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl Iterator<Item = String>) -> Args {
+        let mut args = args.peekable();
+        let command = match args.peek() {
+            Some(arg) if !arg.starts_with("--") => args.next(),
+            _ => None,
+        };
+
+        let mut flags = HashMap::new();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .unwrap_or_else(|| panic!("Expected a --flag, got: {}", arg))
+                .to_string();
+
+            let value = match args.peek() {
+                Some(next) if !next.starts_with("--") => args.next(),
+                _ => None,
+            };
+            flags.insert(name, value);
+        }
+
+        Args { command, flags }
+    }
+
+    /// True if the flag was given, with or without a value
+    pub fn has(&self, name: &str) -> bool {
+        self.flags.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.flags.get(name).and_then(|value| value.as_deref())
+    }
+
+    /// Parses the value of a flag, panics if the value is not valid for `T`
+    pub fn get_parsed<T: FromStr>(&self, name: &str) -> Option<T>
+    where
+        T::Err: Display,
+    {
+        self.get(name).map(|value| {
+            value
+                .parse()
+                .unwrap_or_else(|err| panic!("Invalid value for --{}: {} ({})", name, value, err))
+        })
+    }
+
+    /// Like `get_parsed`, but panics if the flag is missing
+    pub fn require<T: FromStr>(&self, name: &str) -> T
+    where
+        T::Err: Display,
+    {
+        self.get_parsed(name)
+            .unwrap_or_else(|| panic!("Missing required flag --{}", name))
+    }
+}
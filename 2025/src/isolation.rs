@@ -0,0 +1,191 @@
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+/// Limits a child process runs a day under
+pub struct Limits {
+    /// Wall-clock time before the child is killed
+    pub timeout: Duration,
+
+    /// Address space limit of the child, in bytes
+    pub memory_bytes: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            timeout: Duration::from_secs(60),
+            memory_bytes: None,
+        }
+    }
+}
+
+/// How a day run in a child process ended
+pub enum Outcome {
+    /// The printed lines of the solution
+    Solved(Vec<String>),
+
+    /// There is no solution for the day
+    NoSolution,
+
+    /// Panic message
+    Panicked(String),
+
+    TimedOut,
+
+    /// The child died without reporting back, e.g. when it ran out of memory
+    Crashed(String),
+}
+
+impl Outcome {
+    pub fn status(&self) -> &'static str {
+        match self {
+            Outcome::Solved(_) => "ok",
+            Outcome::NoSolution => "missing",
+            Outcome::Panicked(_) => "panicked",
+            Outcome::TimedOut => "timed out",
+            Outcome::Crashed(_) => "crashed",
+        }
+    }
+
+    pub fn details(&self) -> String {
+        match self {
+            Outcome::Panicked(message) | Outcome::Crashed(message) => message.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+// first byte of the child's report, followed by the lines or panic message
+const SOLVED_TAG: u8 = b'S';
+const NO_SOLUTION_TAG: u8 = b'N';
+const PANICKED_TAG: u8 = b'P';
+
+/// Runs `solve` in a forked child process within `limits`. The child reports its lines or
+/// panic message back through a pipe, and is killed if it runs past the timeout.
+pub fn run_isolated(limits: &Limits, solve: impl FnOnce() -> Option<Vec<String>>) -> Outcome {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+        panic!(
+            "Failed to create a pipe: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    let (read_fd, write_fd) = (fds[0], fds[1]);
+
+    let pid = unsafe { libc::fork() };
+    if pid < 0 {
+        panic!("Failed to fork: {}", std::io::Error::last_os_error());
+    }
+
+    if pid == 0 {
+        unsafe { libc::close(read_fd) };
+        run_child(limits, write_fd, solve);
+    }
+
+    unsafe { libc::close(write_fd) };
+    let (report, timed_out) = read_report(read_fd, limits.timeout);
+    unsafe { libc::close(read_fd) };
+
+    if timed_out {
+        unsafe { libc::kill(pid, libc::SIGKILL) };
+    }
+
+    let mut status = 0;
+    unsafe { libc::waitpid(pid, &mut status, 0) };
+
+    if timed_out {
+        return Outcome::TimedOut;
+    }
+
+    let text = String::from_utf8_lossy(report.get(1..).unwrap_or_default()).to_string();
+    match report.first() {
+        Some(&SOLVED_TAG) => Outcome::Solved(text.lines().map(String::from).collect()),
+        Some(&NO_SOLUTION_TAG) => Outcome::NoSolution,
+        Some(&PANICKED_TAG) => Outcome::Panicked(text),
+        _ if libc::WIFSIGNALED(status)
+            && libc::WTERMSIG(status) == libc::SIGABRT
+            && limits.memory_bytes.is_some() =>
+        {
+            Outcome::Crashed(String::from("aborted, most likely out of memory"))
+        }
+        _ if libc::WIFSIGNALED(status) => {
+            Outcome::Crashed(format!("killed by signal {}", libc::WTERMSIG(status)))
+        }
+        _ => Outcome::Crashed(format!("exited with status {}", libc::WEXITSTATUS(status))),
+    }
+}
+
+/// Runs in the child: applies the memory limit, solves and reports back, then exits without
+/// running any of the parent's cleanup
+fn run_child(limits: &Limits, write_fd: i32, solve: impl FnOnce() -> Option<Vec<String>>) -> ! {
+    if let Some(memory_bytes) = limits.memory_bytes {
+        let limit = libc::rlimit {
+            rlim_cur: memory_bytes,
+            rlim_max: memory_bytes,
+        };
+        unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) };
+    }
+
+    // the panic message is sent to the parent instead of printed
+    std::panic::set_hook(Box::new(|_| {}));
+    let report = match catch_unwind(AssertUnwindSafe(solve)) {
+        Ok(Some(lines)) => [vec![SOLVED_TAG], lines.join("\n").into_bytes()].concat(),
+        Ok(None) => vec![NO_SOLUTION_TAG],
+        Err(payload) => [vec![PANICKED_TAG], panic_message(&*payload).into_bytes()].concat(),
+    };
+
+    let mut written = 0;
+    while written < report.len() {
+        let remaining = &report[written..];
+        let count = unsafe { libc::write(write_fd, remaining.as_ptr().cast(), remaining.len()) };
+        if count <= 0 {
+            break;
+        }
+        written += count as usize;
+    }
+
+    unsafe { libc::_exit(0) }
+}
+
+/// The message a panic was raised with
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| String::from("unknown panic")),
+    }
+}
+
+/// Reads the child's report until it closes the pipe. Gives up once `timeout` passes, returning
+/// true as the second value.
+fn read_report(read_fd: i32, timeout: Duration) -> (Vec<u8>, bool) {
+    let deadline = Instant::now() + timeout;
+    let mut report = vec![];
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut poll_fd = libc::pollfd {
+            fd: read_fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = remaining.as_millis().min(i32::MAX as u128) as i32;
+        let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+        if ready < 0 && std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted {
+            continue;
+        }
+        if ready <= 0 {
+            return (report, true);
+        }
+
+        let count = unsafe { libc::read(read_fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        if count <= 0 {
+            return (report, false);
+        }
+        report.extend_from_slice(&buffer[..count as usize]);
+    }
+}
@@ -1,11 +1,235 @@
+mod bench;
+#[cfg(feature = "bigint")]
+mod bigint;
+mod cli;
+mod isolation;
+mod perf;
+mod plugin;
+mod plugin_abi;
 mod runner;
+mod server;
 mod solution_traits;
 mod solutions;
+mod tui;
+mod usage;
+mod watch;
 
+use std::time::Duration;
+
+use crate::bench::BenchConfig;
+use crate::cli::Args;
+use crate::isolation::Limits;
+use crate::perf::Baseline;
+use crate::plugin::DEFAULT_PLUGIN_DIR;
 use crate::runner::SolutionRunner;
+use crate::server::serve;
+use crate::solution_traits::{read_input, InputSource};
+use crate::solutions::day01::{format_rotations, RotationSearch, SearchObjective};
+use crate::solutions::day04::{AccessRule, Grid, SweepOrder};
+use crate::tui::dashboard;
+use crate::watch::watch;
 
 fn main() {
-    let runner = SolutionRunner::new();
-    // runner.run_and_print(1);
-    runner.run_all_days();
-}
\ No newline at end of file
+    let args = Args::from_env();
+    match args.command.as_deref() {
+        None | Some("all") if args.has("isolate") => {
+            runner(&args).run_all_days_isolated(&isolation_limits(&args))
+        }
+        None | Some("all") if args.has("enforce-budgets") => {
+            if !runner(&args).run_all_days_within_budgets() {
+                std::process::exit(1);
+            }
+        }
+        None | Some("all") => perf::record_solved(&runner(&args).run_all_days()),
+        Some("run") if args.has("isolate") => {
+            let day = args.require("day");
+            runner(&args).run_days_isolated(std::iter::once(day), &isolation_limits(&args));
+        }
+        Some("run") if args.has("enforce-budgets") => {
+            let day = args.require("day");
+            if !runner(&args).run_within_budgets(std::iter::once(day)) {
+                std::process::exit(1);
+            }
+        }
+        Some("run") => {
+            let (day, runner) = (args.require("day"), runner(&args));
+            let solved = runner.run_and_print(day);
+            if runner.uses_inputs_dir() {
+                perf::record_solved(&[(day, solved)]);
+            }
+        }
+        Some("stream") => runner(&args).stream_and_print(args.require("day")),
+        Some("bench") => {
+            let runner = runner(&args);
+            let results = runner.bench(args.get_parsed("day"), &bench_config(&args));
+            if !runner.uses_inputs_dir() {
+                println!("Not recorded, timings of a replaced input are not comparable");
+            } else if !results.is_empty() {
+                perf::record(&results);
+            }
+        }
+        Some("perf-report") => perf_report(&args),
+        Some("export") => runner(&args).export(
+            args.require("day"),
+            args.get("view").unwrap_or("frames"),
+            args.get("out"),
+        ),
+        Some("repl") => runner(&args).repl(args.require("day")),
+        Some("serve") => serve(&runner(&args), args.get_parsed("port").unwrap_or(8080)),
+        Some("tui") => dashboard(&runner(&args)),
+        Some("watch") => watch(&runner(&args), args.require("day")),
+        Some("dial-search") => dial_search(&args),
+        Some("forklift") => forklift(&args),
+        Some(command) => panic!("Unknown command: {}", command),
+    }
+}
+
+/// Runner for the command, loading days from plugins if `--plugins [dir]` is given, reporting
+/// resource usage with `--usage` and reading the input from `--input <path>`, `-` for stdin.
+/// The input is one day's, so `--input` is only accepted by commands on a single `--day`.
+fn runner(args: &Args) -> SolutionRunner {
+    let mut runner = match args.get("input") {
+        Some(input) => {
+            let single_day = match args.command.as_deref() {
+                Some("run" | "stream" | "export" | "repl" | "watch") => true,
+                Some("bench") => args.has("day"),
+                _ => false,
+            };
+            if !single_day {
+                panic!("--input holds a single day's input, it needs a command on one --day");
+            }
+            match (args.command.as_deref(), input) {
+                (Some("repl"), "-") => panic!("repl reads queries from stdin, give --input a file"),
+                (Some("watch"), "-") => panic!("watch needs a file to watch, give --input a file"),
+                _ => {}
+            }
+
+            SolutionRunner::from_input(match InputSource::from_arg(input) {
+                // a stream reads stdin as it goes, other commands may load a day more than once
+                InputSource::Stdin if args.command.as_deref() != Some("stream") => {
+                    InputSource::Bytes(InputSource::read_stdin())
+                }
+                input => input,
+            })
+        }
+        None => SolutionRunner::new(),
+    };
+    if args.has("plugins") {
+        runner = runner.with_plugins(args.get("plugins").unwrap_or(DEFAULT_PLUGIN_DIR));
+    }
+    if args.has("usage") {
+        runner = runner.with_usage();
+    }
+
+    runner
+}
+
+/// Limits for `--isolate` runs, from `--timeout` in seconds and `--memory-mb`
+fn isolation_limits(args: &Args) -> Limits {
+    let mut limits = Limits::default();
+    if let Some(timeout) = args.get_parsed("timeout") {
+        limits.timeout = Duration::from_secs_f64(timeout);
+    }
+    if let Some(memory_mb) = args.get_parsed::<u64>("memory-mb") {
+        limits.memory_bytes = Some(memory_mb << 20);
+    }
+
+    limits
+}
+
+/// Benchmark settings from `--iterations`, `--warmup` and `--cpu`
+fn bench_config(args: &Args) -> BenchConfig {
+    let mut config = BenchConfig::default();
+    if let Some(iterations) = args.get_parsed("iterations") {
+        config.iterations = iterations;
+    }
+    if let Some(warmup) = args.get_parsed("warmup") {
+        config.warmup = warmup;
+    }
+    config.cpu = args.get_parsed("cpu");
+
+    config
+}
+
+/// Compares the latest benchmark run against `--baseline <commit>` or the median of the
+/// `--rolling` previous runs, exiting with an error if a day got slower than `--threshold` percent
+fn perf_report(args: &Args) {
+    let baseline = match args.get("baseline") {
+        Some(commit) => Baseline::Commit(commit.to_string()),
+        None => Baseline::RollingMedian(args.get_parsed("rolling").unwrap_or(5)),
+    };
+
+    if perf::report(&baseline, args.get_parsed("threshold").unwrap_or(10.0)) {
+        std::process::exit(1);
+    }
+}
+
+/// Prints rotation sequences that turn the day 1 dial from `--start` to `--target`
+fn dial_search(args: &Args) {
+    let objective = match args.get_parsed("crossings") {
+        Some(crossings) => SearchObjective::ZeroCrossings(crossings),
+        None => SearchObjective::FewestRotations,
+    };
+
+    let mut search = RotationSearch::new(args.require("start"), args.require("target"), objective);
+    if let Some(dial_size) = args.get_parsed("dial-size") {
+        if dial_size == 0 {
+            panic!("Invalid value for --dial-size: the dial needs at least 1 position");
+        }
+        search.dial_size = dial_size;
+        search.max_magnitude = dial_size - 1;
+    }
+    if let Some(max_rotations) = args.get_parsed("max-rotations") {
+        search.max_rotations = max_rotations;
+    }
+    if let Some(max_magnitude) = args.get_parsed("max-magnitude") {
+        search.max_magnitude = max_magnitude;
+    }
+    if let Some(max_solutions) = args.get_parsed("max-solutions") {
+        search.max_solutions = max_solutions;
+    }
+
+    let solutions = search.solve();
+    if solutions.is_empty() {
+        println!("No rotation sequences found");
+    }
+
+    // each solution is headed by its number, with its rotations in the input file's format below
+    for (i, rotations) in solutions.iter().enumerate() {
+        if rotations.is_empty() {
+            println!("Solution {}: no rotations needed", i + 1);
+        } else {
+            println!("Solution {}:", i + 1);
+            print!("{}", format_rotations(rotations));
+        }
+    }
+}
+
+/// Runs both day 4 counts with a different accessibility rule
+fn forklift(args: &Args) {
+    let input_bytes = match args.get("input") {
+        Some(input) => read_input(&InputSource::from_arg(input), 4, None),
+        None => SolutionRunner::new().read_input(4),
+    };
+    let input = String::from_utf8_lossy(&input_bytes).to_string();
+
+    let mut rule = AccessRule::default();
+    if let Some(neighbourhood) = args.get_parsed("neighbourhood") {
+        rule.neighbourhood = neighbourhood;
+    }
+    if let Some(threshold) = args.get_parsed("threshold") {
+        rule.threshold = threshold;
+    }
+    rule.wrap = args.has("wrap");
+
+    let mut grid = Grid::from_file(&input).with_rule(rule);
+    println!("Accessible rolls: {}", grid.accessible_rolls_of_paper());
+
+    let order = args.get_parsed("order").unwrap_or(SweepOrder::Simultaneous);
+    let report = grid.cleanup(order);
+    println!(
+        "Removed rolls: {} over {} rounds",
+        report.total(),
+        report.rounds()
+    );
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::bench::{format_duration, BenchResult};
+use crate::runner::SolvedDay;
+
+/// History file inside the output directory
+const HISTORY_FILE: &str = "perf_history.jsonl";
+
+/// Directory for generated files, `ADVENT_OUTPUT_DIR` or `output` next to `Cargo.toml`
+pub fn output_dir() -> String {
+    std::env::var("ADVENT_OUTPUT_DIR")
+        .unwrap_or_else(|_| concat!(env!("CARGO_MANIFEST_DIR"), "/output").to_string())
+}
+
+fn history_path() -> String {
+    format!("{}/{}", output_dir(), HISTORY_FILE)
+}
+
+/// How the timings of a `PerfRecord` were taken
+pub const SOURCE_BENCH: &str = "bench";
+pub const SOURCE_RUN: &str = "run";
+
+/// Timings of one day in one run, a line of the history file. Benchmark runs record the mean
+/// of their iterations, normal runs the single time taken.
+pub struct PerfRecord {
+    /// Unix time in nanoseconds the run started, shared by every day of the run
+    pub run: u64,
+
+    /// Process that made the run, which tells apart runs started at the same time
+    pub pid: u32,
+
+    /// `SOURCE_BENCH` or `SOURCE_RUN`, only runs of the same source are compared
+    pub source: String,
+    pub commit: String,
+    pub machine: String,
+    pub day: u8,
+    pub init: Duration,
+    pub part1: Duration,
+    pub part2: Duration,
+}
+
+impl PerfRecord {
+    pub fn total(&self) -> Duration {
+        self.init + self.part1 + self.part2
+    }
+
+    fn to_json(&self) -> String {
+        format!(
+            "{{\"run\":{},\"pid\":{},\"source\":\"{}\",\"commit\":\"{}\",\"machine\":\"{}\",\"day\":{},\"init_ns\":{},\"part1_ns\":{},\"part2_ns\":{}}}",
+            self.run,
+            self.pid,
+            escape(&self.source),
+            escape(&self.commit),
+            escape(&self.machine),
+            self.day,
+            self.init.as_nanos(),
+            self.part1.as_nanos(),
+            self.part2.as_nanos()
+        )
+    }
+
+    fn from_json(line: &str) -> Result<PerfRecord, String> {
+        let fields = parse_flat_object(line)?;
+        let text = |key: &str| -> Result<String, String> {
+            fields
+                .get(key)
+                .cloned()
+                .ok_or(format!("Missing field '{}'", key))
+        };
+        let number = |key: &str| -> Result<u64, String> {
+            text(key)?
+                .parse()
+                .map_err(|_| format!("Field '{}' is not a number", key))
+        };
+
+        // lines written before normal runs were recorded have neither field
+        Ok(PerfRecord {
+            run: number("run")?,
+            pid: number("pid").unwrap_or(0) as u32,
+            source: text("source").unwrap_or_else(|_| String::from(SOURCE_BENCH)),
+            commit: text("commit")?,
+            machine: text("machine")?,
+            day: number("day")? as u8,
+            init: Duration::from_nanos(number("init_ns")?),
+            part1: Duration::from_nanos(number("part1_ns")?),
+            part2: Duration::from_nanos(number("part2_ns")?),
+        })
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Parses a JSON object of string and number values, as written by `PerfRecord::to_json`
+fn parse_flat_object(line: &str) -> Result<HashMap<String, String>, String> {
+    let inner = line
+        .trim()
+        .strip_prefix('{')
+        .and_then(|line| line.strip_suffix('}'))
+        .ok_or("Expected a JSON object")?;
+
+    let mut fields = HashMap::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ',').is_some() {}
+        if chars.peek().is_none() {
+            return Ok(fields);
+        }
+
+        let key = parse_string(&mut chars)?;
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.next() != Some(':') {
+            return Err(format!("Expected ':' after '{}'", key));
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+
+        let value = if chars.peek() == Some(&'"') {
+            parse_string(&mut chars)?
+        } else {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| *c != ',' && !c.is_whitespace()) {
+                number.push(c);
+            }
+            number
+        };
+        fields.insert(key, value);
+    }
+}
+
+fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Result<String, String> {
+    if chars.next() != Some('"') {
+        return Err(String::from("Expected a string"));
+    }
+
+    let mut string = String::new();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(string),
+            Some('\\') => string.extend(chars.next()),
+            Some(c) => string.push(c),
+            None => return Err(String::from("Unterminated string")),
+        }
+    }
+}
+
+/// Short hash of the checked out commit, with `-dirty` if there are uncommitted changes
+fn git_commit() -> String {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    match (
+        git(&["rev-parse", "--short", "HEAD"]),
+        git(&["status", "--porcelain"]),
+    ) {
+        (Some(hash), Some(changes)) if !changes.is_empty() => format!("{}-dirty", hash),
+        (Some(hash), _) => hash,
+        (None, _) => String::from("unknown"),
+    }
+}
+
+/// `/etc/machine-id`, or the host name where there is none
+fn machine_id() -> String {
+    if let Ok(id) = std::fs::read_to_string("/etc/machine-id") {
+        return id.trim().to_string();
+    }
+
+    let mut name = [0u8; 256];
+    unsafe { libc::gethostname(name.as_mut_ptr().cast(), name.len()) };
+    let len = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+    String::from_utf8_lossy(&name[..len]).to_string()
+}
+
+/// Appends the mean timings of a benchmark run to the history file
+pub fn record(results: &[BenchResult]) {
+    let timings: Vec<(u8, Duration, Duration, Duration)> = results
+        .iter()
+        .map(|result| {
+            (
+                result.day,
+                result.init.mean,
+                result.part1.mean,
+                result.part2.mean,
+            )
+        })
+        .collect();
+    let (run, commit) = append(SOURCE_BENCH, &timings);
+
+    println!("Recorded run {} of {} in {}", run, commit, history_path());
+}
+
+/// Appends the timings of a normal run of `days` to the history file
+pub fn record_solved(days: &[(u8, SolvedDay)]) {
+    let timings: Vec<(u8, Duration, Duration, Duration)> = days
+        .iter()
+        .map(|(day, solved)| (*day, solved.init, solved.part1, solved.part2))
+        .collect();
+    append(SOURCE_RUN, &timings);
+}
+
+/// Appends a run's init, part 1 and part 2 timings of each day, returning the run and commit
+fn append(source: &str, timings: &[(u8, Duration, Duration, Duration)]) -> (u64, String) {
+    let run = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("System clock is before 1970")
+        .as_nanos() as u64;
+    let (commit, machine) = (git_commit(), machine_id());
+
+    std::fs::create_dir_all(output_dir())
+        .unwrap_or_else(|err| panic!("Failed to create {}: {}", output_dir(), err));
+    let mut history = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(history_path())
+        .unwrap_or_else(|err| panic!("Failed to open {}: {}", history_path(), err));
+
+    for &(day, init, part1, part2) in timings {
+        let record = PerfRecord {
+            run,
+            pid: std::process::id(),
+            source: source.to_string(),
+            commit: commit.clone(),
+            machine: machine.clone(),
+            day,
+            init,
+            part1,
+            part2,
+        };
+        writeln!(history, "{}", record.to_json())
+            .unwrap_or_else(|err| panic!("Failed to write {}: {}", history_path(), err));
+    }
+
+    (run, commit)
+}
+
+/// What the latest run is compared against
+pub enum Baseline {
+    /// The most recent earlier run of a commit, by hash prefix
+    Commit(String),
+
+    /// The median of this many earlier runs
+    RollingMedian(usize),
+}
+
+/// Compares the latest run on this machine against `baseline`, earlier runs of the same source,
+/// printing a row per day. Days slower by more than `threshold_percent` are flagged, and the
+/// result is whether any were.
+pub fn report(baseline: &Baseline, threshold_percent: f64) -> bool {
+    let history = std::fs::read_to_string(history_path())
+        .unwrap_or_else(|err| panic!("Failed to read {}: {}", history_path(), err));
+
+    // other machines' timings are not comparable
+    let machine = machine_id();
+    let records: Vec<PerfRecord> = history
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            PerfRecord::from_json(line)
+                .unwrap_or_else(|err| panic!("Bad line {} in {}: {}", i + 1, history_path(), err))
+        })
+        .filter(|record| record.machine == machine)
+        .collect();
+
+    let Some(latest) = records.iter().max_by_key(|record| (record.run, record.pid)) else {
+        println!("No runs recorded on this machine yet");
+        return false;
+    };
+
+    let (latest_run, source) = ((latest.run, latest.pid), latest.source.clone());
+    let (latest, earlier): (Vec<&PerfRecord>, Vec<&PerfRecord>) = records
+        .iter()
+        .filter(|record| record.source == source)
+        .partition(|record| (record.run, record.pid) == latest_run);
+    println!("Latest {} timings", source);
+
+    println!(
+        "{:>3}  {:>11}  {:>11}  {:>8}  Status",
+        "Day", "Latest", "Baseline", "Change"
+    );
+    let mut regressed = false;
+    for record in latest {
+        let mut past: Vec<&PerfRecord> = earlier
+            .iter()
+            .copied()
+            .filter(|past| past.day == record.day)
+            .collect();
+        past.sort_by_key(|past| (past.run, past.pid));
+
+        let baseline_time = match baseline {
+            Baseline::Commit(commit) => past
+                .iter()
+                .rev()
+                .find(|past| past.commit.starts_with(commit.as_str()))
+                .map(|past| past.total()),
+            Baseline::RollingMedian(window) => {
+                let mut totals: Vec<Duration> = past
+                    .iter()
+                    .rev()
+                    .take(*window)
+                    .map(|past| past.total())
+                    .collect();
+                totals.sort();
+                totals.get(totals.len() / 2).copied()
+            }
+        };
+
+        let Some(baseline_time) = baseline_time else {
+            println!(
+                "{:>3}  {:>11}  {:>11}  {:>8}  no baseline",
+                record.day,
+                format_duration(record.total()),
+                "-",
+                "-"
+            );
+            continue;
+        };
+
+        let change = (record.total().as_secs_f64() / baseline_time.as_secs_f64() - 1.0) * 100.0;
+        let status = if change > threshold_percent {
+            regressed = true;
+            "SLOWER"
+        } else {
+            "ok"
+        };
+        println!(
+            "{:>3}  {:>11}  {:>11}  {:>+7.1}%  {}",
+            record.day,
+            format_duration(record.total()),
+            format_duration(baseline_time),
+            change,
+            status
+        );
+    }
+
+    regressed
+}
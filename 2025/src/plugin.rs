@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::plugin_abi::{
+    entry_symbol, PluginApi, PluginInput, FRAME_SEPARATOR, PLUGIN_ABI_VERSION,
+};
+use crate::solution_traits::{InputSource, Solution};
+
+/// Counts loads, to give every loaded copy of a library its own path
+static LOADS: AtomicUsize = AtomicUsize::new(0);
+
+/// View names of every plugin loaded so far. `Solution::views` hands out static names, so
+/// names copied out of a library are leaked here, once each.
+static VIEW_NAMES: Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+/// Where `build_plugin.sh` puts the libraries
+pub const DEFAULT_PLUGIN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/plugins");
+
+/// Path of a day's library inside `plugin_dir`
+pub fn plugin_path(plugin_dir: &str, day: u8) -> String {
+    format!("{}/libday{:02}.so", plugin_dir, day)
+}
+
+/// A day's shared library, closed once it and every solution created from it are dropped
+pub struct Plugin {
+    library: *mut c_void,
+    api: PluginApi,
+    day: u8,
+}
+
+impl Plugin {
+    /// Opens the day's library at `path`.
+    ///
+    /// `dlopen` hands back the already open library for a path it has seen, so the library is
+    /// copied to a fresh path first. This way a rebuilt library is picked up by loading again.
+    pub fn load(path: &str, day: u8) -> Result<Rc<Plugin>, String> {
+        let copy = std::env::temp_dir().join(format!(
+            "advent-day{:02}-{}-{}.so",
+            day,
+            std::process::id(),
+            LOADS.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::copy(path, &copy).map_err(|err| format!("Failed to copy {}: {}", path, err))?;
+
+        let copy_path =
+            CString::new(copy.to_string_lossy().as_bytes()).expect("Temp path contains a NUL byte");
+        let library =
+            unsafe { libc::dlopen(copy_path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+        // the mapping stays valid once opened, so the copy is not needed any more
+        let _ = std::fs::remove_file(&copy);
+        if library.is_null() {
+            return Err(format!("Failed to load {}: {}", path, dl_error()));
+        }
+
+        let symbol = CString::new(entry_symbol(day)).expect("Symbol contains a NUL byte");
+        let entry = unsafe { libc::dlsym(library, symbol.as_ptr()) };
+        if entry.is_null() {
+            unsafe { libc::dlclose(library) };
+            return Err(format!("{} does not export {}", path, entry_symbol(day)));
+        }
+
+        let entry: extern "C" fn() -> PluginApi = unsafe { std::mem::transmute(entry) };
+        let api = entry();
+        if api.abi_version != PLUGIN_ABI_VERSION {
+            unsafe { libc::dlclose(library) };
+            return Err(format!(
+                "{} was built for plugin ABI {}, expected {}",
+                path, api.abi_version, PLUGIN_ABI_VERSION
+            ));
+        }
+
+        Ok(Rc::new(Plugin { library, api, day }))
+    }
+
+    /// Loads the day's solution through the library
+    pub fn init(self: &Rc<Self>, input: &InputSource) -> Box<dyn Solution> {
+        let input = PluginInput::new(input);
+        let solution = (self.api.init)(&input, self.day);
+        if solution.is_null() {
+            panic!(
+                "Plugin for day {} panicked while loading its input",
+                self.day
+            );
+        }
+
+        Box::new(PluginSolution {
+            plugin: Rc::clone(self),
+            solution,
+        })
+    }
+
+    /// Takes ownership of a string returned by the library
+    fn take_string(&self, string: *mut c_char, during: &str) -> String {
+        if string.is_null() {
+            panic!("Plugin for day {} panicked in {}", self.day, during);
+        }
+
+        let owned = unsafe { CStr::from_ptr(string) }
+            .to_string_lossy()
+            .to_string();
+        (self.api.free_string)(string);
+        owned
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        unsafe { libc::dlclose(self.library) };
+    }
+}
+
+/// A static copy of a view name
+fn intern_view_name(name: &str) -> &'static str {
+    let mut names = VIEW_NAMES.lock().expect("View names lock is poisoned");
+    let names = names.get_or_insert_with(HashSet::new);
+    match names.get(name) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(interned);
+            interned
+        }
+    }
+}
+
+fn dl_error() -> String {
+    let err = unsafe { libc::dlerror() };
+    if err.is_null() {
+        return String::from("unknown error");
+    }
+
+    unsafe { CStr::from_ptr(err) }.to_string_lossy().to_string()
+}
+
+/// A solution living inside a plugin, which keeps the plugin loaded
+struct PluginSolution {
+    plugin: Rc<Plugin>,
+    solution: *mut c_void,
+}
+
+impl Solution for PluginSolution {
+    fn part1(&mut self) -> String {
+        let answer = (self.plugin.api.part1)(self.solution);
+        self.plugin.take_string(answer, "part 1")
+    }
+
+    fn part2(&mut self) -> String {
+        let answer = (self.plugin.api.part2)(self.solution);
+        self.plugin.take_string(answer, "part 2")
+    }
+
+    fn diagnostics(&self) -> Vec<String> {
+        let lines = (self.plugin.api.diagnostics)(self.solution);
+        let lines = self.plugin.take_string(lines, "diagnostics");
+        lines.lines().map(String::from).collect()
+    }
+
+    fn query(&mut self, command: &str) -> Result<String, String> {
+        let command =
+            CString::new(command).map_err(|_| String::from("Query contains a NUL byte"))?;
+        let mut is_error = false;
+        let answer = (self.plugin.api.query)(self.solution, command.as_ptr(), &mut is_error);
+        let answer = self.plugin.take_string(answer, "a query");
+
+        if is_error {
+            Err(answer)
+        } else {
+            Ok(answer)
+        }
+    }
+
+    fn visualize(&mut self, view: &str) -> Option<Vec<String>> {
+        let view = CString::new(view).ok()?;
+        let mut found = true;
+        let frames = (self.plugin.api.visualize)(self.solution, view.as_ptr(), &mut found);
+        let frames = self.plugin.take_string(frames, "a view");
+
+        found.then(|| {
+            frames
+                .split_terminator(FRAME_SEPARATOR)
+                .map(String::from)
+                .collect()
+        })
+    }
+
+    fn views(&self) -> Vec<&'static str> {
+        let views = (self.plugin.api.views)(self.solution);
+        let views = self.plugin.take_string(views, "views");
+        views.lines().map(intern_view_name).collect()
+    }
+}
+
+impl Drop for PluginSolution {
+    fn drop(&mut self) {
+        (self.plugin.api.destroy)(self.solution);
+    }
+}
@@ -0,0 +1,85 @@
+use std::ffi::{c_char, c_void};
+
+use crate::solution_traits::InputSource;
+
+/// Bumped whenever `PluginApi` changes, so the runner refuses libraries built against another layout
+pub const PLUGIN_ABI_VERSION: u32 = 3;
+
+/// Table of functions a day plugin returns from its `advent_dayNN` entry point.
+///
+/// Solutions are passed around as opaque pointers from `init`. Strings returned by the plugin
+/// are owned by it and must be handed back to `free_string`. A null string means the solution
+/// panicked inside the plugin.
+#[repr(C)]
+pub struct PluginApi {
+    pub abi_version: u32,
+
+    /// Loads the solution from its input, returns null on panic
+    pub init: extern "C" fn(input: *const PluginInput, day: u8) -> *mut c_void,
+
+    pub part1: extern "C" fn(solution: *mut c_void) -> *mut c_char,
+
+    pub part2: extern "C" fn(solution: *mut c_void) -> *mut c_char,
+
+    /// Diagnostics lines joined by newlines
+    pub diagnostics: extern "C" fn(solution: *mut c_void) -> *mut c_char,
+
+    /// Answers a query, setting `is_error` if the returned string is an error message
+    pub query: extern "C" fn(
+        solution: *mut c_void,
+        command: *const c_char,
+        is_error: *mut bool,
+    ) -> *mut c_char,
+
+    /// Names of the views, joined by newlines
+    pub views: extern "C" fn(solution: *mut c_void) -> *mut c_char,
+
+    /// Renders a view as frames, each followed by `FRAME_SEPARATOR`. Clears `found` if the
+    /// solution has no such view.
+    pub visualize:
+        extern "C" fn(solution: *mut c_void, view: *const c_char, found: *mut bool) -> *mut c_char,
+
+    pub free_string: extern "C" fn(string: *mut c_char),
+
+    pub destroy: extern "C" fn(solution: *mut c_void),
+}
+
+/// Ends each frame returned by `visualize`, frames are multi-line text so a newline can't
+pub const FRAME_SEPARATOR: char = '\x1e';
+
+/// Kinds of `PluginInput`
+pub const INPUT_DIRECTORY: u8 = 0;
+pub const INPUT_FILE: u8 = 1;
+pub const INPUT_BYTES: u8 = 2;
+
+/// A day's input passed to `init`. `data` is the path of an inputs directory or file, or the
+/// input itself, borrowed for the duration of the call.
+#[repr(C)]
+pub struct PluginInput {
+    pub kind: u8,
+    pub data: *const u8,
+    pub len: usize,
+}
+
+impl PluginInput {
+    /// Borrows `input` for a call to `init`
+    pub fn new(input: &InputSource) -> PluginInput {
+        let (kind, data) = match input {
+            InputSource::Directory(dir) => (INPUT_DIRECTORY, dir.as_bytes()),
+            InputSource::File(path) => (INPUT_FILE, path.as_bytes()),
+            InputSource::Bytes(bytes) => (INPUT_BYTES, bytes.as_slice()),
+            InputSource::Stdin => unreachable!("SolutionRunner::with_input reads stdin into bytes"),
+        };
+
+        PluginInput {
+            kind,
+            data: data.as_ptr(),
+            len: data.len(),
+        }
+    }
+}
+
+/// Name of the entry point a day's plugin exports
+pub fn entry_symbol(day: u8) -> String {
+    format!("advent_day{:02}", day)
+}
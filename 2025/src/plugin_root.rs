@@ -0,0 +1,165 @@
+//! Crate root for building a single day as a shared library, see `build_plugin.sh`.
+//!
+//! The day is picked with `--cfg plugin_day="NN"`, and its solution is exported through the
+//! `advent_dayNN` entry point described in `plugin_abi`.
+
+// only the entry point is used from each day, the rest of its module is dead code here
+#![allow(dead_code)]
+
+mod plugin_abi;
+mod solution_traits;
+
+mod solutions {
+    #[cfg(plugin_day = "01")]
+    pub mod day01;
+    #[cfg(plugin_day = "02")]
+    pub mod day02;
+    #[cfg(plugin_day = "03")]
+    pub mod day03;
+    #[cfg(plugin_day = "04")]
+    pub mod day04;
+    #[cfg(plugin_day = "05")]
+    pub mod day05;
+    #[cfg(plugin_day = "06")]
+    pub mod day06;
+    #[cfg(plugin_day = "07")]
+    pub mod day07;
+}
+
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::plugin_abi::{
+    PluginApi, PluginInput, FRAME_SEPARATOR, INPUT_DIRECTORY, INPUT_FILE, PLUGIN_ABI_VERSION,
+};
+use crate::solution_traits::{InputSource, Solution, SolutionFactory};
+
+/// Exports `$factory` as this library's day, under the entry point `$symbol`
+macro_rules! export_day {
+    ($symbol:ident, $factory:ty) => {
+        #[no_mangle]
+        pub extern "C" fn $symbol() -> PluginApi {
+            plugin_api::<$factory>()
+        }
+    };
+}
+
+#[cfg(plugin_day = "01")]
+export_day!(advent_day01, solutions::day01::Day1);
+#[cfg(plugin_day = "02")]
+export_day!(advent_day02, solutions::day02::Day2);
+#[cfg(plugin_day = "03")]
+export_day!(advent_day03, solutions::day03::Day3);
+#[cfg(plugin_day = "04")]
+export_day!(advent_day04, solutions::day04::Day4);
+#[cfg(plugin_day = "05")]
+export_day!(advent_day05, solutions::day05::Day5);
+#[cfg(plugin_day = "06")]
+export_day!(advent_day06, solutions::day06::Day6);
+#[cfg(plugin_day = "07")]
+export_day!(advent_day07, solutions::day07::Day7);
+
+fn plugin_api<F: SolutionFactory>() -> PluginApi {
+    PluginApi {
+        abi_version: PLUGIN_ABI_VERSION,
+        init: init::<F>,
+        part1,
+        part2,
+        diagnostics,
+        query,
+        views,
+        visualize,
+        free_string,
+        destroy,
+    }
+}
+
+/// Solutions cross the library boundary as a thin pointer to the boxed trait object
+fn solution<'a>(solution: *mut c_void) -> &'a mut Box<dyn Solution> {
+    unsafe { &mut *solution.cast::<Box<dyn Solution>>() }
+}
+
+/// Hands a string to the host, or null if producing it panicked. Panics must not unwind into
+/// the host, so they are caught here.
+fn to_host(produce: impl FnOnce() -> String) -> *mut c_char {
+    match catch_unwind(AssertUnwindSafe(produce)) {
+        Ok(string) => CString::new(string.replace('\0', ""))
+            .expect("NUL bytes were removed")
+            .into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// The source the host passed to `init`, copied out of its borrowed data
+fn input_source(input: &PluginInput) -> InputSource {
+    let data = unsafe { std::slice::from_raw_parts(input.data, input.len) };
+    let text = || String::from_utf8_lossy(data).to_string();
+    match input.kind {
+        INPUT_DIRECTORY => InputSource::Directory(text()),
+        INPUT_FILE => InputSource::File(text()),
+        _ => InputSource::Bytes(data.to_vec()),
+    }
+}
+
+extern "C" fn init<F: SolutionFactory>(input: *const PluginInput, day: u8) -> *mut c_void {
+    let input = input_source(unsafe { &*input });
+    match catch_unwind(|| F::init(&input, day)) {
+        Ok(solution) => Box::into_raw(Box::new(solution)).cast(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+extern "C" fn part1(handle: *mut c_void) -> *mut c_char {
+    to_host(|| solution(handle).part1())
+}
+
+extern "C" fn part2(handle: *mut c_void) -> *mut c_char {
+    to_host(|| solution(handle).part2())
+}
+
+extern "C" fn diagnostics(handle: *mut c_void) -> *mut c_char {
+    to_host(|| solution(handle).diagnostics().join("\n"))
+}
+
+extern "C" fn query(
+    handle: *mut c_void,
+    command: *const c_char,
+    is_error: *mut bool,
+) -> *mut c_char {
+    let command = unsafe { CStr::from_ptr(command) }.to_string_lossy();
+    to_host(|| match solution(handle).query(&command) {
+        Ok(answer) => answer,
+        Err(err) => {
+            unsafe { *is_error = true };
+            err
+        }
+    })
+}
+
+extern "C" fn views(handle: *mut c_void) -> *mut c_char {
+    to_host(|| solution(handle).views().join("\n"))
+}
+
+extern "C" fn visualize(handle: *mut c_void, view: *const c_char, found: *mut bool) -> *mut c_char {
+    let view = unsafe { CStr::from_ptr(view) }.to_string_lossy();
+    to_host(|| match solution(handle).visualize(&view) {
+        Some(frames) => frames
+            .iter()
+            .map(|frame| format!("{}{}", frame, FRAME_SEPARATOR))
+            .collect(),
+        None => {
+            unsafe { *found = false };
+            String::new()
+        }
+    })
+}
+
+extern "C" fn free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+extern "C" fn destroy(handle: *mut c_void) {
+    drop(unsafe { Box::from_raw(handle.cast::<Box<dyn Solution>>()) });
+}
@@ -1,41 +1,481 @@
-use crate::{solution_traits::Solution, solutions::get_solution_for_day};
+use std::hint::black_box;
+use std::io::{BufRead, BufReader, Write};
+use std::time::{Duration, Instant};
+
+use crate::{
+    bench::{format_duration, pin_to_cpu, BenchConfig, BenchResult, Stats},
+    isolation::{run_isolated, Limits, Outcome},
+    plugin::{plugin_path, Plugin},
+    solution_traits::{input_path, read_input, InputSource, Solution},
+    solutions::{
+        get_budget_for_day, get_solution_for_day, get_stream_solver_for_day, has_solution_for_day,
+        TOTAL_BUDGET,
+    },
+    usage::Usage,
+};
 
 const NUM_CHALLENGES: u8 = 12;
 
+/// A day's answers and printed lines, with the wall time of each phase
+pub struct SolvedDay {
+    pub lines: Vec<String>,
+    pub answers: [String; 2],
+    pub diagnostics: Vec<String>,
+    pub init: Duration,
+    pub part1: Duration,
+    pub part2: Duration,
+}
+
 /// Runner struct to manage execution of solutions
+#[derive(Clone)]
 pub struct SolutionRunner {
-    path_to_inputs: String,
+    /// Where each day's input is read from
+    input: InputSource,
+
+    /// Directory of `libdayNN.so` plugins to load days from, instead of the built-in ones
+    plugin_dir: Option<String>,
+
+    /// Print the CPU time, memory and page faults of each day's init and parts
+    report_usage: bool,
 }
 
 impl SolutionRunner {
     pub fn new() -> Self {
         let path_to_inputs = std::env::var("ADVENT_INPUTS_DIR")
             .expect("Failed to get ADVENT_INPUTS_DIR from environment");
-        Self { path_to_inputs }
+        Self::from_input(InputSource::Directory(path_to_inputs))
+    }
+
+    /// Reads inputs from `input`, without needing `ADVENT_INPUTS_DIR`. Stdin is left unread, so
+    /// it can only be read once, see `with_input`.
+    pub fn from_input(input: InputSource) -> Self {
+        Self {
+            input,
+            plugin_dir: None,
+            report_usage: false,
+        }
+    }
+
+    /// Loads days from the plugins in `plugin_dir`. Each run loads the library again, so a
+    /// rebuilt plugin is picked up without restarting.
+    pub fn with_plugins(mut self, plugin_dir: &str) -> Self {
+        self.plugin_dir = Some(plugin_dir.to_string());
+        self
+    }
+
+    /// Prints the resources used by each day's init, part 1 and part 2 below its answers
+    pub fn with_usage(mut self) -> Self {
+        self.report_usage = true;
+        self
+    }
+
+    /// Reads inputs from `input` instead of `ADVENT_INPUTS_DIR`. Stdin is read up front, as a
+    /// day may be loaded more than once.
+    pub fn with_input(mut self, input: InputSource) -> Self {
+        self.input = match input {
+            InputSource::Stdin => InputSource::Bytes(InputSource::read_stdin()),
+            input => input,
+        };
+        self
+    }
+
+    pub fn uses_plugins(&self) -> bool {
+        self.plugin_dir.is_some()
+    }
+
+    /// Whether days read their own inputs from an inputs directory, rather than an input given
+    /// in place of theirs. Only those timings are comparable between runs.
+    pub fn uses_inputs_dir(&self) -> bool {
+        matches!(self.input, InputSource::Directory(_))
+    }
+
+    /// Days that have a solution, built in or as a plugin
+    pub fn days(&self) -> Vec<u8> {
+        (1..=NUM_CHALLENGES)
+            .filter(|&day| match &self.plugin_dir {
+                Some(plugin_dir) => std::path::Path::new(&plugin_path(plugin_dir, day)).exists(),
+                None => has_solution_for_day(day),
+            })
+            .collect()
+    }
+
+    /// Loads a day's solution, from its plugin if plugins are in use
+    pub fn solution(&self, day: u8) -> Option<Box<dyn Solution>> {
+        let Some(plugin_dir) = &self.plugin_dir else {
+            return get_solution_for_day(&self.input, day);
+        };
+
+        let path = plugin_path(plugin_dir, day);
+        if !std::path::Path::new(&path).exists() {
+            return None;
+        }
+
+        let plugin = Plugin::load(&path, day).unwrap_or_else(|err| panic!("{}", err));
+        Some(plugin.init(&self.input))
+    }
+
+    /// Loads and runs a day, giving the lines to print, or `None` if the day has no solution
+    pub fn solve(&self, day: u8) -> Option<SolvedDay> {
+        let start = Usage::now();
+        let mut solution = self.solution(day)?;
+        let loaded = Usage::now();
+        let part1 = solution.part1();
+        let solved_part1 = Usage::now();
+        let part2 = solution.part2();
+        let solved_part2 = Usage::now();
+
+        let diagnostics = solution.diagnostics();
+        let mut lines = vec![
+            format!("Day {}", day),
+            format!("\tPart 1: {}", part1),
+            format!("\tPart 2: {}", part2),
+        ];
+        lines.extend(diagnostics.iter().map(|line| format!("\t{}", line)));
+
+        let (init_usage, part1_usage, part2_usage) = (
+            loaded.since(&start),
+            solved_part1.since(&loaded),
+            solved_part2.since(&solved_part1),
+        );
+        if self.report_usage {
+            lines.push(format!("\tInit usage:   {}", init_usage));
+            lines.push(format!("\tPart 1 usage: {}", part1_usage));
+            lines.push(format!("\tPart 2 usage: {}", part2_usage));
+        }
+
+        Some(SolvedDay {
+            lines,
+            answers: [part1, part2],
+            diagnostics,
+            init: init_usage.wall,
+            part1: part1_usage.wall,
+            part2: part2_usage.wall,
+        })
     }
 
-    #[allow(dead_code)]
-    pub fn run_and_print(&self, day: u8) {
-        let solution = get_solution_for_day(&self.path_to_inputs, day)
-            .expect(&format!("Failed to get solution for day {}", day));
+    /// Runs a day and prints its answers, returning the printed lines
+    pub fn run_and_print(&self, day: u8) -> SolvedDay {
+        let solved = self
+            .solve(day)
+            .unwrap_or_else(|| panic!("Failed to get solution for day {}", day));
 
-        print_solution(solution, day);
+        solved.lines.iter().for_each(|line| println!("{}", line));
+        solved
     }
 
-    pub fn run_all_days(&self) {
+    /// Runs and prints every day that has a solution, returning them by day
+    pub fn run_all_days(&self) -> Vec<(u8, SolvedDay)> {
+        let mut solved_days = vec![];
         for day in 1..=NUM_CHALLENGES {
-            let solution = match get_solution_for_day(&self.path_to_inputs, day) {
-                Some(s) => s,
+            let solved = match self.solve(day) {
+                Some(solved) => solved,
                 None => continue,
             };
 
-            print_solution(solution, day);
+            solved.lines.iter().for_each(|line| println!("{}", line));
+            solved_days.push((day, solved));
+        }
+
+        solved_days
+    }
+
+    /// Runs each day in its own child process, so a panic, hang or runaway allocation in one
+    /// day does not stop the rest. Ends with a status table of every day that has a solution.
+    pub fn run_days_isolated(&self, days: impl Iterator<Item = u8>, limits: &Limits) {
+        let mut statuses = vec![];
+        for day in days {
+            let start = Instant::now();
+            let outcome = run_isolated(limits, || self.solve(day).map(|solved| solved.lines));
+            let elapsed = start.elapsed();
+
+            match &outcome {
+                Outcome::NoSolution => continue,
+                Outcome::Solved(lines) => lines.iter().for_each(|line| println!("{}", line)),
+                _ => {
+                    let summary = format!("{} {}", outcome.status(), outcome.details());
+                    println!("Day {}\n\t{}", day, summary.trim_end());
+                }
+            }
+            statuses.push((day, outcome, elapsed));
+        }
+
+        println!();
+        println!("{:>3}  {:<9}  {:>9}  Details", "Day", "Status", "Time");
+        for (day, outcome, elapsed) in statuses {
+            let row = format!(
+                "{:>3}  {:<9}  {:>8.3}s  {}",
+                day,
+                outcome.status(),
+                elapsed.as_secs_f64(),
+                outcome.details()
+            );
+            println!("{}", row.trim_end());
+        }
+    }
+
+    /// Runs every day, see `run_days_isolated`
+    pub fn run_all_days_isolated(&self, limits: &Limits) {
+        self.run_days_isolated(1..=NUM_CHALLENGES, limits);
+    }
+
+    /// Runs each day and checks its init and parts against the day's time budget, and all days
+    /// together against the total budget. Ends with a table of how much of its budget each day
+    /// used, and returns whether every budget was kept.
+    pub fn run_within_budgets(&self, days: impl Iterator<Item = u8>) -> bool {
+        let mut rows = vec![];
+        for day in days {
+            let Some(solved) = self.solve(day) else {
+                continue;
+            };
+            solved.lines.iter().for_each(|line| println!("{}", line));
+
+            let budget = get_budget_for_day(day);
+            let phases = [
+                ("init", solved.init),
+                ("part 1", solved.part1),
+                ("part 2", solved.part2),
+            ];
+            for (phase, time) in phases {
+                if let Some(budget) = budget.filter(|&budget| time > budget) {
+                    println!(
+                        "\tOver budget: {} took {} of {}",
+                        phase,
+                        format_duration(time),
+                        format_duration(budget)
+                    );
+                }
+            }
+            rows.push((day, budget, solved));
+        }
+
+        println!();
+        println!(
+            "{:>3}  {:>11}  {:>11}  {:>11}  {:>11}  {:>8}  Status",
+            "Day", "Budget", "Init", "Part 1", "Part 2", "Used"
+        );
+        let mut within_budgets = true;
+        let mut total = Duration::ZERO;
+        for (day, budget, solved) in rows {
+            total += solved.init + solved.part1 + solved.part2;
+            let slowest = solved.init.max(solved.part1).max(solved.part2);
+            let (budget, used, status) = match budget {
+                Some(budget) => {
+                    let used = slowest.as_secs_f64() / budget.as_secs_f64() * 100.0;
+                    let status = if slowest > budget { "OVER" } else { "ok" };
+                    (format_duration(budget), format!("{:.1}%", used), status)
+                }
+                None => (String::from("-"), String::from("-"), "no budget"),
+            };
+            within_budgets &= status != "OVER";
+
+            println!(
+                "{:>3}  {:>11}  {:>11}  {:>11}  {:>11}  {:>8}  {}",
+                day,
+                budget,
+                format_duration(solved.init),
+                format_duration(solved.part1),
+                format_duration(solved.part2),
+                used,
+                status
+            );
+        }
+
+        let total_status = if total > TOTAL_BUDGET { "OVER" } else { "ok" };
+        within_budgets &= total <= TOTAL_BUDGET;
+        println!(
+            "Total: {} of {} ({:.1}%) {}",
+            format_duration(total),
+            format_duration(TOTAL_BUDGET),
+            total.as_secs_f64() / TOTAL_BUDGET.as_secs_f64() * 100.0,
+            total_status
+        );
+
+        within_budgets
+    }
+
+    /// Runs every day, see `run_within_budgets`
+    pub fn run_all_days_within_budgets(&self) -> bool {
+        self.run_within_budgets(1..=NUM_CHALLENGES)
+    }
+
+    /// Benchmarks a day, building a fresh solution every iteration as the parts may change
+    /// it. Returns `None` if the day has no solution.
+    pub fn bench_day(&self, day: u8, config: &BenchConfig) -> Option<BenchResult> {
+        let (mut init, mut part1, mut part2) = (vec![], vec![], vec![]);
+        for iteration in 0..config.warmup + config.iterations {
+            let start = Instant::now();
+            let mut solution = self.solution(day)?;
+            let loaded = Instant::now();
+            black_box(solution.part1());
+            let solved_part1 = Instant::now();
+            black_box(solution.part2());
+            let solved_part2 = Instant::now();
+
+            if iteration >= config.warmup {
+                init.push(loaded - start);
+                part1.push(solved_part1 - loaded);
+                part2.push(solved_part2 - solved_part1);
+            }
+        }
+
+        Some(BenchResult {
+            day,
+            init: Stats::from_samples(&init),
+            part1: Stats::from_samples(&part1),
+            part2: Stats::from_samples(&part2),
+        })
+    }
+
+    /// Benchmarks one day, or every day if `day` is `None`, printing the stats of each
+    pub fn bench(&self, day: Option<u8>, config: &BenchConfig) -> Vec<BenchResult> {
+        if config.iterations == 0 {
+            panic!("A benchmark needs at least one iteration");
+        }
+        if let Some(cpu) = config.cpu {
+            pin_to_cpu(cpu).unwrap_or_else(|err| panic!("{}", err));
+            println!("Pinned to CPU {}", cpu);
+        }
+
+        let days = match day {
+            Some(day) => day..=day,
+            None => 1..=NUM_CHALLENGES,
+        };
+
+        let mut results = vec![];
+        for day in days {
+            let Some(result) = self.bench_day(day, config) else {
+                continue;
+            };
+
+            println!(
+                "Day {}: {} iterations after {} warmup",
+                result.day, config.iterations, config.warmup
+            );
+            println!("\tInit:   {}", result.init);
+            println!("\tPart 1: {}", result.part1);
+            println!("\tPart 2: {}", result.part2);
+            results.push(result);
+        }
+
+        results
+    }
+
+    /// Prints a day's visualization frames, or writes them to `out` if given
+    pub fn export(&self, day: u8, view: &str, out: Option<&str>) {
+        let mut solution = self
+            .solution(day)
+            .unwrap_or_else(|| panic!("Failed to get solution for day {}", day));
+        let frames = solution
+            .visualize(view)
+            .unwrap_or_else(|| panic!("Day {} has no '{}' view", day, view));
+        let text = frames.join("\n\n");
+
+        match out {
+            Some(path) => std::fs::write(path, text + "\n")
+                .unwrap_or_else(|err| panic!("Failed to write {}: {}", path, err)),
+            None => println!("{}", text),
+        }
+    }
+
+    /// Reads queries for a day from stdin until `quit` or end of input. `reload` loads the day
+    /// again, picking up changes to its input or plugin.
+    pub fn repl(&self, day: u8) {
+        let mut solution = self
+            .solution(day)
+            .unwrap_or_else(|| panic!("Failed to get solution for day {}", day));
+
+        let stdin = std::io::stdin();
+        let mut line = String::new();
+        loop {
+            print!("day{:02}> ", day);
+            std::io::stdout().flush().expect("Failed to flush stdout");
+
+            line.clear();
+            let read = stdin.read_line(&mut line).expect("Failed to read query");
+            let command = line.trim();
+            if read == 0 || command == "quit" || command == "exit" {
+                break;
+            }
+            if command.is_empty() {
+                continue;
+            }
+            if command == "reload" {
+                solution = self
+                    .solution(day)
+                    .unwrap_or_else(|| panic!("Failed to get solution for day {}", day));
+                println!("Reloaded day {}", day);
+                continue;
+            }
+
+            match solution.query(command) {
+                Ok(answer) => println!("{}", answer),
+                Err(err) => println!("error: {}", err),
+            }
+        }
+    }
+
+    /// Directory holding the day's input file, or `None` if the input is not read from a file
+    pub fn input_dir(&self, day: u8) -> Option<String> {
+        match &self.input {
+            InputSource::Directory(inputs_dir) => Some(format!("{}/{:02}", inputs_dir, day)),
+            InputSource::File(path) => Some(match std::path::Path::new(path).parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+                _ => String::from("."),
+            }),
+            InputSource::Stdin | InputSource::Bytes(_) => None,
+        }
+    }
+
+    /// Name of the input file inside `input_dir`, or `None` if any `.txt` file there may be the
+    /// day's input
+    pub fn input_file_name(&self) -> Option<String> {
+        match &self.input {
+            InputSource::File(path) => std::path::Path::new(path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            _ => None,
+        }
+    }
+
+    /// Reads the day's input
+    pub fn read_input(&self, day: u8) -> Vec<u8> {
+        read_input(&self.input, day, None)
+    }
+
+    /// Streams the day's input through its streaming solver
+    pub fn stream_and_print(&self, day: u8) {
+        match &self.input {
+            InputSource::Directory(inputs_dir) => {
+                let path = input_path(inputs_dir, day, None);
+                stream_and_print(day, &mut *open_stream(&path));
+            }
+            InputSource::File(path) => stream_and_print(day, &mut *open_stream(path)),
+            InputSource::Stdin => stream_and_print(day, &mut *open_stream("-")),
+            InputSource::Bytes(bytes) => stream_and_print(day, &mut bytes.as_slice()),
         }
     }
 }
 
-fn print_solution(mut solution : Box<dyn Solution>, day : u8) {
+/// Opens `path` for buffered reading, `-` reads from stdin
+fn open_stream(path: &str) -> Box<dyn BufRead> {
+    const STREAM_BUFFER_SIZE: usize = 1 << 20;
+
+    if path == "-" {
+        return Box::new(BufReader::with_capacity(STREAM_BUFFER_SIZE, std::io::stdin()));
+    }
+
+    let file = std::fs::File::open(path)
+        .unwrap_or_else(|err| panic!("Failed to open input file {}: {}", path, err));
+    Box::new(BufReader::with_capacity(STREAM_BUFFER_SIZE, file))
+}
+
+/// Runs a day's streaming solver over `reader`, without loading the whole input
+fn stream_and_print(day: u8, reader: &mut dyn BufRead) {
+    let solver = get_stream_solver_for_day(day)
+        .unwrap_or_else(|| panic!("Day {} has no streaming mode", day));
+    let (part1, part2) = solver(reader);
+
     println!("Day {}", day);
-    println!("\tPart 1: {}", solution.part1());
-    println!("\tPart 2: {}", solution.part2());
+    println!("\tPart 1: {}", part1);
+    println!("\tPart 2: {}", part2);
 }
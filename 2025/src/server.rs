@@ -0,0 +1,232 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::time::Duration;
+
+use crate::isolation::panic_message;
+use crate::runner::{SolutionRunner, SolvedDay};
+use crate::solution_traits::InputSource;
+use crate::solutions::{get_budget_for_day, get_stream_solver_for_day};
+
+/// Largest input accepted by `POST /days/{n}/solve`
+const MAX_BODY_BYTES: usize = 64 << 20;
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A JSON response with its HTTP status code
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Response {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: format!("{{\"error\":{}}}", json_string(message)),
+        }
+    }
+}
+
+/// Serves the days of `runner` over HTTP on localhost, one request at a time:
+///
+/// - `GET /days` lists the days that have a solution
+/// - `GET /days/{n}` solves a day with its input from the inputs directory
+/// - `POST /days/{n}/solve` solves a day with the request body as its input
+pub fn serve(runner: &SolutionRunner, port: u16) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .unwrap_or_else(|err| panic!("Failed to listen on port {}: {}", port, err));
+    println!(
+        "Serving on http://{}",
+        listener.local_addr().expect("Listener has an address")
+    );
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => handle(runner, stream),
+            Err(err) => eprintln!("Failed to accept a connection: {}", err),
+        }
+    }
+}
+
+fn handle(runner: &SolutionRunner, mut stream: TcpStream) {
+    let response = match read_request(&mut stream) {
+        Ok((method, path, body)) => {
+            let response = route(runner, &method, &path, body);
+            println!("{} {} -> {}", method, path, response.status);
+            response
+        }
+        Err(response) => response,
+    };
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.body.len()
+    );
+    if let Err(err) = stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(response.body.as_bytes()))
+    {
+        eprintln!("Failed to send a response: {}", err);
+    }
+}
+
+/// Reads the method, path and body of a request, or the error response to send instead
+fn read_request(stream: &mut TcpStream) -> Result<(String, String, Vec<u8>), Response> {
+    let bad_request = |message: &str| Response::error(400, message);
+
+    stream
+        .set_read_timeout(Some(READ_TIMEOUT))
+        .map_err(|err| bad_request(&err.to_string()))?;
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|err| bad_request(&err.to_string()))?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("Malformed request line"));
+    };
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let method = method.to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        reader
+            .read_line(&mut header)
+            .map_err(|err| bad_request(&err.to_string()))?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| bad_request("Invalid Content-Length"))?;
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_BYTES {
+        return Err(Response::error(413, "Input is too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|err| bad_request(&err.to_string()))?;
+
+    Ok((method, path, body))
+}
+
+fn route(runner: &SolutionRunner, method: &str, path: &str, body: Vec<u8>) -> Response {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let day = match segments.get(1).map(|day| day.parse::<u8>()) {
+        Some(Ok(day)) => Some(day),
+        Some(Err(_)) => return Response::error(404, &format!("No such day: {}", segments[1])),
+        None => None,
+    };
+
+    match (method, segments.as_slice()) {
+        ("GET", ["days"]) => list_days(runner),
+        ("GET", ["days", _]) => solve(runner, day.expect("Route has a day")),
+        ("POST", ["days", _, "solve"]) => solve_input(runner, day.expect("Route has a day"), body),
+        (_, ["days"] | ["days", _] | ["days", _, "solve"]) => {
+            Response::error(405, &format!("{} is not allowed on {}", method, path))
+        }
+        _ => Response::error(404, &format!("No such route: {}", path)),
+    }
+}
+
+fn list_days(runner: &SolutionRunner) -> Response {
+    let days: Vec<String> = runner
+        .days()
+        .into_iter()
+        .map(|day| {
+            let budget = get_budget_for_day(day)
+                .map(|budget| budget.as_nanos().to_string())
+                .unwrap_or_else(|| String::from("null"));
+            format!(
+                "{{\"day\":{},\"budget_ns\":{},\"streaming\":{}}}",
+                day,
+                budget,
+                get_stream_solver_for_day(day).is_some()
+            )
+        })
+        .collect();
+
+    Response::ok(format!("{{\"days\":[{}]}}", days.join(",")))
+}
+
+/// Solves a day, turning a panic into an error response
+fn solve(runner: &SolutionRunner, day: u8) -> Response {
+    match catch_unwind(AssertUnwindSafe(|| runner.solve(day))) {
+        Ok(Some(solved)) => Response::ok(solved_json(day, &solved)),
+        Ok(None) => Response::error(404, &format!("Day {} has no solution", day)),
+        Err(payload) => Response::error(500, &panic_message(&*payload)),
+    }
+}
+
+/// Solves a day with `input` in place of its input file
+fn solve_input(runner: &SolutionRunner, day: u8, input: Vec<u8>) -> Response {
+    solve(&runner.clone().with_input(InputSource::Bytes(input)), day)
+}
+
+fn solved_json(day: u8, solved: &SolvedDay) -> String {
+    let diagnostics: Vec<String> = solved
+        .diagnostics
+        .iter()
+        .map(|line| json_string(line))
+        .collect();
+
+    format!(
+        "{{\"day\":{},\"part1\":{},\"part2\":{},\"diagnostics\":[{}],\"timings_ns\":{{\"init\":{},\"part1\":{},\"part2\":{}}}}}",
+        day,
+        json_string(&solved.answers[0]),
+        json_string(&solved.answers[1]),
+        diagnostics.join(","),
+        solved.init.as_nanos(),
+        solved.part1.as_nanos(),
+        solved.part2.as_nanos()
+    )
+}
+
+/// Quotes and escapes `text` as a JSON string
+fn json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
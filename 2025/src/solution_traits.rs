@@ -1,18 +1,87 @@
+use std::io::{BufRead, Read};
+
 
 /// Trait defining the interface for solutions to each day's challenge
 pub trait Solution {
     fn part1(&mut self) -> String;
     fn part2(&mut self) -> String;
+
+    /// Extra details about the last run of the parts, printed below the answers
+    fn diagnostics(&self) -> Vec<String> {
+        vec![]
+    }
+
+    /// Renders the named view of this day as text frames, or `None` if there is no such view
+    fn visualize(&mut self, _view: &str) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Names of the views `visualize` renders, each without options
+    fn views(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    /// Answers an interactive query about the loaded input
+    fn query(&mut self, _command: &str) -> Result<String, String> {
+        Err(String::from("This day does not answer queries"))
+    }
+}
+
+/// Where a day's input is read from
+#[derive(Clone)]
+pub enum InputSource {
+    /// Inputs directory laid out as `{dir}/{NN}/dayNN.txt`
+    Directory(String),
+
+    /// Input file at any path
+    File(String),
+
+    /// Standard input, which can only be read once
+    Stdin,
+
+    /// Input already in memory
+    Bytes(Vec<u8>),
+}
+
+impl InputSource {
+    /// Source named on the command line, `-` for stdin or else a file path
+    pub fn from_arg(arg : &str) -> InputSource {
+        match arg {
+            "-" => InputSource::Stdin,
+            path => InputSource::File(path.to_string()),
+        }
+    }
+
+    /// Reads all of stdin, which leaves nothing for a later read
+    pub fn read_stdin() -> Vec<u8> {
+        let mut input_bytes = vec![];
+        std::io::stdin().read_to_end(&mut input_bytes).expect("Failed to read input from stdin");
+        input_bytes
+    }
 }
 
 /// Factory trait to initialize solutions
 pub trait SolutionFactory {
-    fn init(inputs_dir : &str, day : u8) -> Box<dyn Solution>;
+    fn init(input : &InputSource, day : u8) -> Box<dyn Solution>;
 }
 
-/// Utility function to read input file for a given day
-pub fn read_input(inputs_dir : &str, day : u8, special_file : Option<String>) -> Vec<u8> {
+/// Solves both parts by reading the input incrementally instead of loading it all at once
+pub type StreamSolver = fn(&mut dyn BufRead) -> (String, String);
+
+/// Utility function to get the path of the input file for a given day
+pub fn input_path(inputs_dir : &str, day : u8, special_file : Option<String>) -> String {
     let file = special_file.unwrap_or(format!("day{:02}.txt", day));
-    let input_path = format!("{}/{:02}/{}", inputs_dir, day, file);
+    format!("{}/{:02}/{}", inputs_dir, day, file)
+}
+
+/// Utility function to read the input for a given day, `special_file` only applies to an inputs
+/// directory
+pub fn read_input(input : &InputSource, day : u8, special_file : Option<String>) -> Vec<u8> {
+    let input_path = match input {
+        InputSource::Directory(inputs_dir) => input_path(inputs_dir, day, special_file),
+        InputSource::File(path) => path.clone(),
+        InputSource::Stdin => return InputSource::read_stdin(),
+        InputSource::Bytes(input_bytes) => return input_bytes.clone(),
+    };
     std::fs::read(&input_path).expect(&format!("Failed to read input file: {}", input_path))
 }
\ No newline at end of file
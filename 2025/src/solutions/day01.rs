@@ -1,9 +1,12 @@
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+use std::io::BufRead;
 
-type DialNumT = u16;
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
+
+pub type DialNumT = u16;
 const DIAL_MAX: DialNumT = 100;
 const DIAL_START: DialNumT = 50;
 
+#[derive(Clone, Copy)]
 pub enum Rotation {
     /// Dial rotation left (this many digits)
     Left(DialNumT),
@@ -21,36 +24,46 @@ impl Rotation {
     }
 
     pub fn apply(&self, dial: DialNumT) -> DialNumT {
+        self.apply_with_size(dial, DIAL_MAX)
+    }
+
+    pub fn get_clicks(&self, dial: DialNumT) -> u32 {
+        self.get_clicks_with_size(dial, DIAL_MAX)
+    }
+
+    /// Same as `apply`, but for a dial with `dial_size` positions
+    pub fn apply_with_size(&self, dial: DialNumT, dial_size: DialNumT) -> DialNumT {
         match self {
             Rotation::Left(count) => {
-                let real_diff = count % DIAL_MAX;
+                let real_diff = count % dial_size;
                 if real_diff > dial {
-                    DIAL_MAX - (real_diff - dial)
+                    dial_size - (real_diff - dial)
                 } else {
                     dial - real_diff
                 }
             }
             Rotation::Right(count) => {
-                let real_diff = count % DIAL_MAX;
-                (dial + real_diff) % DIAL_MAX
+                let real_diff = count % dial_size;
+                ((dial as u32 + real_diff as u32) % dial_size as u32) as DialNumT
             }
         }
     }
 
-    pub fn get_clicks(&self, dial: DialNumT) -> u32 {
+    /// Same as `get_clicks`, but for a dial with `dial_size` positions
+    pub fn get_clicks_with_size(&self, dial: DialNumT, dial_size: DialNumT) -> u32 {
         match self {
             Rotation::Left(count) => {
-                let mut clicks = count / DIAL_MAX;
-                let real_diff = count % DIAL_MAX;
+                let mut clicks = count / dial_size;
+                let real_diff = count % dial_size;
                 if real_diff >= dial && dial != 0 {
                     clicks += 1;
                 }
                 clicks as u32
             }
             Rotation::Right(count) => {
-                let mut clicks = count / DIAL_MAX;
-                let real_diff = count % DIAL_MAX;
-                if real_diff + dial >= DIAL_MAX {
+                let mut clicks = count / dial_size;
+                let real_diff = count % dial_size;
+                if real_diff as u32 + dial as u32 >= dial_size as u32 {
                     clicks += 1
                 }
                 clicks as u32
@@ -85,6 +98,263 @@ impl From<&str> for Rotation {
     }
 }
 
+impl std::fmt::Display for Rotation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rotation::Left(count) => write!(f, "L{}", count),
+            Rotation::Right(count) => write!(f, "R{}", count),
+        }
+    }
+}
+
+/// Writes rotations in the same line format `parse_rotation_file` reads
+pub fn format_rotations(rotations: &[Rotation]) -> String {
+    rotations
+        .iter()
+        .map(|rotation| format!("{}\n", rotation))
+        .collect()
+}
+
+/// What a rotation search is looking for
+pub enum SearchObjective {
+    /// Sequences using as few rotations as possible
+    FewestRotations,
+    /// Sequences whose `get_clicks` total is exactly this many zero crossings
+    ZeroCrossings(u32),
+}
+
+/// Searches for rotation sequences that turn a dial from `start` to `target`
+pub struct RotationSearch {
+    pub dial_size: DialNumT,
+    pub start: DialNumT,
+    pub target: DialNumT,
+    /// Longest sequence to consider
+    pub max_rotations: usize,
+    /// Largest count a single rotation may use
+    pub max_magnitude: DialNumT,
+    pub objective: SearchObjective,
+    /// Stop after finding this many sequences
+    pub max_solutions: usize,
+}
+
+impl RotationSearch {
+    pub fn new(start: DialNumT, target: DialNumT, objective: SearchObjective) -> RotationSearch {
+        RotationSearch {
+            dial_size: DIAL_MAX,
+            start,
+            target,
+            max_rotations: 4,
+            max_magnitude: DIAL_MAX - 1,
+            objective,
+            max_solutions: 10,
+        }
+    }
+
+    /// Returns the matching sequences, shortest first
+    pub fn solve(&self) -> Vec<Vec<Rotation>> {
+        assert!(self.dial_size > 0, "Dial size must be at least 1");
+        assert!(
+            self.start < self.dial_size && self.target < self.dial_size,
+            "Start {} and target {} must be on a dial of size {}",
+            self.start,
+            self.target,
+            self.dial_size
+        );
+
+        let mut solutions = vec![];
+        let mut sequence = vec![];
+        match self.objective {
+            SearchObjective::FewestRotations => {
+                let distances = self.distances_to_target();
+                if let Some(steps) = distances[self.start as usize] {
+                    if steps <= self.max_rotations {
+                        self.collect_shortest(
+                            self.start,
+                            steps,
+                            &distances,
+                            &mut sequence,
+                            &mut solutions,
+                        );
+                    }
+                }
+            }
+            SearchObjective::ZeroCrossings(crossings) => {
+                let table = self.crossings_table(crossings);
+                for steps in 0..=self.max_rotations {
+                    if solutions.len() >= self.max_solutions {
+                        break;
+                    }
+                    self.collect_crossings(
+                        self.start,
+                        steps,
+                        crossings,
+                        &table,
+                        &mut sequence,
+                        &mut solutions,
+                    );
+                }
+            }
+        }
+
+        solutions
+    }
+
+    fn moves(&self) -> impl Iterator<Item = Rotation> + '_ {
+        (1..=self.max_magnitude).flat_map(|count| [Rotation::Left(count), Rotation::Right(count)])
+    }
+
+    /// Fewest rotations needed to reach the target from every dial position
+    ///
+    /// Every left rotation is undone by a right rotation of the same count, so searching
+    /// outwards from the target gives the distances towards it
+    fn distances_to_target(&self) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.dial_size as usize];
+        let mut queue = std::collections::VecDeque::new();
+        distances[self.target as usize] = Some(0);
+        queue.push_back(self.target);
+
+        while let Some(dial) = queue.pop_front() {
+            let next_distance = distances[dial as usize].unwrap() + 1;
+            for rotation in self.moves() {
+                let next = rotation.apply_with_size(dial, self.dial_size) as usize;
+                if distances[next].is_none() {
+                    distances[next] = Some(next_distance);
+                    queue.push_back(next as DialNumT);
+                }
+            }
+        }
+
+        distances
+    }
+
+    fn collect_shortest(
+        &self,
+        dial: DialNumT,
+        steps_left: usize,
+        distances: &[Option<usize>],
+        sequence: &mut Vec<Rotation>,
+        solutions: &mut Vec<Vec<Rotation>>,
+    ) {
+        if solutions.len() >= self.max_solutions {
+            return;
+        }
+
+        if steps_left == 0 {
+            solutions.push(sequence.clone());
+            return;
+        }
+
+        for rotation in self.moves() {
+            let next = rotation.apply_with_size(dial, self.dial_size);
+            if distances[next as usize] == Some(steps_left - 1) {
+                sequence.push(rotation);
+                self.collect_shortest(next, steps_left - 1, distances, sequence, solutions);
+                sequence.pop();
+            }
+        }
+    }
+
+    /// `table[steps][dial][clicks]` is true when the target can be reached from `dial` in
+    /// exactly `steps` rotations that cross zero exactly `clicks` times
+    fn crossings_table(&self, crossings: u32) -> Vec<Vec<Vec<bool>>> {
+        let positions = self.dial_size as usize;
+        let clicks = crossings as usize + 1;
+        let mut table = vec![vec![vec![false; clicks]; positions]; self.max_rotations + 1];
+        table[0][self.target as usize][0] = true;
+
+        for steps in 1..=self.max_rotations {
+            for dial in 0..positions {
+                for rotation in self.moves() {
+                    let used =
+                        rotation.get_clicks_with_size(dial as DialNumT, self.dial_size) as usize;
+                    let next = rotation.apply_with_size(dial as DialNumT, self.dial_size) as usize;
+                    for remaining in used..clicks {
+                        if table[steps - 1][next][remaining - used] {
+                            table[steps][dial][remaining] = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        table
+    }
+
+    fn collect_crossings(
+        &self,
+        dial: DialNumT,
+        steps_left: usize,
+        clicks_left: u32,
+        table: &[Vec<Vec<bool>>],
+        sequence: &mut Vec<Rotation>,
+        solutions: &mut Vec<Vec<Rotation>>,
+    ) {
+        if solutions.len() >= self.max_solutions
+            || !table[steps_left][dial as usize][clicks_left as usize]
+        {
+            return;
+        }
+
+        if steps_left == 0 {
+            solutions.push(sequence.clone());
+            return;
+        }
+
+        for rotation in self.moves() {
+            let used = rotation.get_clicks_with_size(dial, self.dial_size);
+            if used > clicks_left {
+                continue;
+            }
+
+            let next = rotation.apply_with_size(dial, self.dial_size);
+            sequence.push(rotation);
+            self.collect_crossings(
+                next,
+                steps_left - 1,
+                clicks_left - used,
+                table,
+                sequence,
+                solutions,
+            );
+            sequence.pop();
+        }
+    }
+}
+
+/// Solves both parts in a single pass over `reader`, one rotation line at a time
+///
+/// Only the current line is held in memory, so inputs of any length can be piped through
+pub fn stream_rotations(reader: &mut dyn BufRead) -> (String, String) {
+    let mut current = DIAL_START;
+    let mut stops_at_zero: u64 = 0;
+    let mut clicks_at_zero: u64 = 0;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .expect("Failed to read rotation from stream");
+        if read == 0 {
+            break;
+        }
+
+        let rotation_str = line.trim();
+        if rotation_str.is_empty() {
+            continue;
+        }
+
+        let rotation = Rotation::from(rotation_str);
+        clicks_at_zero += rotation.get_clicks(current) as u64;
+        current = rotation.apply(current);
+        if current == 0 {
+            stops_at_zero += 1;
+        }
+    }
+
+    (stops_at_zero.to_string(), clicks_at_zero.to_string())
+}
+
 pub struct Day1 {
     rotations: Vec<Rotation>,
 }
@@ -116,9 +386,9 @@ impl Solution for Day1 {
 }
 
 impl SolutionFactory for Day1 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
         let rotations = Rotation::parse_rotation_file(input);
 
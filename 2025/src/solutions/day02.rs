@@ -1,4 +1,4 @@
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
 
 fn is_invalid_id(id : u64) -> bool {
     let id_str = id.to_string();
@@ -130,9 +130,9 @@ impl Solution for Day2 {
 }
 
 impl SolutionFactory for Day2 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
         let id_ranges = IDRange::from_csv(&input);
         Box::new(Day2 { id_ranges })
@@ -1,4 +1,4 @@
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
 
 struct BatteryBank {
     line: String,
@@ -187,9 +187,9 @@ impl Solution for Day3 {
 }
 
 impl SolutionFactory for Day3 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
 
         let banks = BatteryBank::from_file(&input);
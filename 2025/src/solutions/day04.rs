@@ -1,7 +1,122 @@
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+use std::str::FromStr;
+
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
 
 const ACCESSIBLE_LIMIT: usize = 4;
 
+/// Offsets of the 8 slots surrounding a slot
+const MOORE_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Offsets of the 4 slots directly above, below, left and right of a slot
+const VON_NEUMANN_OFFSETS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Which slots count as the neighbours of a slot
+#[derive(Clone)]
+pub enum Neighbourhood {
+    /// Only the orthogonally adjacent slots
+    VonNeumann,
+
+    /// All 8 surrounding slots
+    Moore,
+
+    /// Any set of (x, y) offsets from the slot
+    Custom(Vec<(i32, i32)>),
+}
+
+impl Neighbourhood {
+    pub fn offsets(&self) -> &[(i32, i32)] {
+        match self {
+            Neighbourhood::VonNeumann => &VON_NEUMANN_OFFSETS,
+            Neighbourhood::Moore => &MOORE_OFFSETS,
+            Neighbourhood::Custom(offsets) => offsets,
+        }
+    }
+}
+
+/// Parses `moore`, `von-neumann`, or custom offsets written as `dx,dy;dx,dy;...`
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "moore" => return Ok(Neighbourhood::Moore),
+            "von-neumann" => return Ok(Neighbourhood::VonNeumann),
+            _ => {}
+        }
+
+        value
+            .split(';')
+            .map(|offset| {
+                let (dx, dy) = offset
+                    .split_once(',')
+                    .ok_or(format!("Offset is not in dx,dy form: {}", offset))?;
+                let dx = dx.trim().parse().map_err(|_| format!("Invalid dx: {}", dx))?;
+                let dy = dy.trim().parse().map_err(|_| format!("Invalid dy: {}", dy))?;
+                Ok((dx, dy))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Neighbourhood::Custom)
+    }
+}
+
+/// Decides when a roll of paper can be reached by a forklift
+#[derive(Clone)]
+pub struct AccessRule {
+    pub neighbourhood: Neighbourhood,
+
+    /// A roll is accessible when fewer than this many of its neighbours are rolls of paper
+    pub threshold: usize,
+
+    /// Neighbours past an edge of the grid wrap around to the opposite edge
+    pub wrap: bool,
+}
+
+impl Default for AccessRule {
+    fn default() -> Self {
+        AccessRule {
+            neighbourhood: Neighbourhood::Moore,
+            threshold: ACCESSIBLE_LIMIT,
+            wrap: false,
+        }
+    }
+}
+
+/// Order rolls of paper are removed in during a cleanup
+#[derive(Clone, Copy)]
+pub enum SweepOrder {
+    /// Every roll accessible at the start of a round is removed in that round
+    Simultaneous,
+
+    /// Sweep rows top to bottom, removing a roll as soon as it is reached, so removals earlier in
+    /// a sweep can free up rolls later in the same sweep
+    RowMajor,
+
+    /// Same as `RowMajor`, but sweeping columns left to right
+    ColumnMajor,
+}
+
+impl FromStr for SweepOrder {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "simultaneous" => Ok(SweepOrder::Simultaneous),
+            "row-major" => Ok(SweepOrder::RowMajor),
+            "column-major" => Ok(SweepOrder::ColumnMajor),
+            _ => Err(format!("Unknown sweep order: {}", value)),
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy)]
 enum GridSlot {
@@ -37,8 +152,42 @@ fn create_grid_row(line: &str) -> GridRow {
     line.chars().map(|c| GridSlot::from(c)).collect()
 }
 
-struct Grid {
+#[derive(Clone)]
+pub struct Grid {
     rows: Vec<GridRow>,
+    rule: AccessRule,
+}
+
+/// Result of cleaning up a grid
+#[derive(Default)]
+pub struct CleanupReport {
+    /// Positions of the rolls removed in each round (or sweep), in the order they were removed
+    pub removals: Vec<Vec<(usize, usize)>>,
+}
+
+impl CleanupReport {
+    pub fn rounds(&self) -> usize {
+        self.removals.len()
+    }
+
+    pub fn removed_per_round(&self) -> Vec<usize> {
+        self.removals.iter().map(|round| round.len()).collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.removals.iter().map(|round| round.len()).sum()
+    }
+}
+
+/// Most rounds that `round_label` has a single character for
+const MAX_LABELLED_ROUNDS: usize = 35;
+
+/// Label for a removal round in an annotated grid: `1`-`9`, then `a`-`z`
+fn round_label(round: usize) -> char {
+    match round {
+        1..=9 => char::from(b'0' + round as u8),
+        _ => char::from(b'a' + (round - 10) as u8),
+    }
 }
 
 impl Grid {
@@ -49,11 +198,20 @@ impl Grid {
             .map(|line| create_grid_row(line))
             .collect();
 
-        Grid { rows }
+        Grid {
+            rows,
+            rule: AccessRule::default(),
+        }
+    }
+
+    /// Uses `rule` instead of the puzzle's rule to decide which rolls are accessible
+    pub fn with_rule(mut self, rule: AccessRule) -> Grid {
+        self.rule = rule;
+        self
     }
 
     pub fn width(&self) -> usize {
-        self.rows[0].len()
+        self.rows.first().map_or(0, |row| row.len())
     }
 
     pub fn height(&self) -> usize {
@@ -67,7 +225,7 @@ impl Grid {
 
         for y in 0..max_y {
             for x in 0..max_x {
-                if self.is_accessible(x as i32, y as i32) {
+                if self.is_accessible(x, y) {
                     count += 1;
                 }
             }
@@ -76,80 +234,238 @@ impl Grid {
         count
     }
 
-    fn get(&self, x: i32, y: i32) -> Option<GridSlot> {
-        if x < 0 || y < 0 {
+    /// Position of the slot at `offset` from (x, y), if it is on the grid
+    fn neighbour(&self, x: usize, y: usize, offset: (i32, i32)) -> Option<(usize, usize)> {
+        let width = self.width() as i32;
+        let height = self.height() as i32;
+        if width == 0 || height == 0 {
             return None;
         }
 
-        let x = x as usize;
-        let y = y as usize;
+        let mut nx = x as i32 + offset.0;
+        let mut ny = y as i32 + offset.1;
 
-        if x >= self.width() || y >= self.height() {
+        if self.rule.wrap {
+            nx = nx.rem_euclid(width);
+            ny = ny.rem_euclid(height);
+        } else if nx < 0 || ny < 0 || nx >= width || ny >= height {
             return None;
         }
 
-        Some(self.rows[y][x])
+        Some((nx as usize, ny as usize))
     }
 
-    fn is_accessible(&self, x: i32, y: i32) -> bool {
-        let slot = match self.get(x, y) {
-            Some(s) => s,
-            None => return false,
-        };
+    fn is_roll_of_paper(&self, x: usize, y: usize) -> bool {
+        self.rows[y][x].is_roll_of_paper()
+    }
 
-        match slot {
-            GridSlot::Empty => false,
-            GridSlot::RollOfPaper => {
-                let mut adjacent = 0;
-
-                let coords = vec![
-                    (x - 1, y - 1),
-                    (x, y - 1),
-                    (x + 1, y - 1),
-                    (x - 1, y),
-                    (x + 1, y),
-                    (x - 1, y + 1),
-                    (x, y + 1),
-                    (x + 1, y + 1),
-                ];
-                assert_eq!(coords.len(), 8);
-
-                coords.iter().for_each(|(x, y)| {
-                    if let Some(slot) = self.get(*x, *y) {
-                        if slot.is_roll_of_paper() {
-                            adjacent += 1;
-                        }
-                    }
-                });
-
-                adjacent < ACCESSIBLE_LIMIT
+    fn is_accessible(&self, x: usize, y: usize) -> bool {
+        self.is_roll_of_paper(x, y) && self.adjacent_rolls_of_paper(x, y) < self.rule.threshold
+    }
+
+    fn adjacent_rolls_of_paper(&self, x: usize, y: usize) -> usize {
+        self.rule
+            .neighbourhood
+            .offsets()
+            .iter()
+            .filter_map(|&offset| self.neighbour(x, y, offset))
+            .filter(|&(nx, ny)| self.is_roll_of_paper(nx, ny))
+            .count()
+    }
+
+    /// Removes accessible rolls of paper until none are left
+    ///
+    /// Instead of rescanning the neighbours of every slot, we keep a count of adjacent rolls per
+    /// slot so a removal only has to update the slots around it.
+    pub fn cleanup(&mut self, order: SweepOrder) -> CleanupReport {
+        let width = self.width();
+        let height = self.height();
+
+        let mut adjacent = vec![0; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                if self.is_roll_of_paper(x, y) {
+                    adjacent[y * width + x] = self.adjacent_rolls_of_paper(x, y);
+                }
+            }
+        }
+
+        match order {
+            SweepOrder::Simultaneous => self.cleanup_in_rounds(&mut adjacent),
+            SweepOrder::RowMajor => {
+                let positions = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+                self.cleanup_in_sweeps(&mut adjacent, positions.collect())
+            }
+            SweepOrder::ColumnMajor => {
+                let positions = (0..width).flat_map(|x| (0..height).map(move |y| (x, y)));
+                self.cleanup_in_sweeps(&mut adjacent, positions.collect())
             }
         }
     }
 
-    fn cleanup(&mut self) -> usize {
-        let mut count = 0;
-        let max_x = self.width();
-        let max_y = self.height();
+    /// Each round removes every roll that became accessible in the previous round, so only
+    /// the neighbours of removed rolls are ever looked at again
+    fn cleanup_in_rounds(&mut self, adjacent: &mut [usize]) -> CleanupReport {
+        let width = self.width();
+        let mut round: Vec<(usize, usize)> = (0..self.height())
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| {
+                self.is_roll_of_paper(x, y) && adjacent[y * width + x] < self.rule.threshold
+            })
+            .collect();
+
+        let mut report = CleanupReport::default();
+        while !round.is_empty() {
+            let mut next_round = vec![];
+            for &(x, y) in round.iter() {
+                self.remove_and_update(x, y, adjacent, &mut next_round);
+            }
+
+            report.removals.push(round);
+            round = next_round;
+        }
 
+        report
+    }
+
+    /// Visits `positions` in order, removing accessible rolls as they are reached, until a
+    /// full sweep removes nothing
+    fn cleanup_in_sweeps(
+        &mut self,
+        adjacent: &mut [usize],
+        positions: Vec<(usize, usize)>,
+    ) -> CleanupReport {
+        let width = self.width();
+        let mut newly_accessible = vec![];
+        let mut report = CleanupReport::default();
 
         loop {
-            let original_count = count;
-            for y in 0..max_y {
-                for x in 0..max_x {
-                    if self.is_accessible(x as i32, y as i32) {
-                        count += 1;
-                        self.remove_paper(x, y);
-                    }
+            let mut sweep = vec![];
+            for &(x, y) in positions.iter() {
+                if self.is_roll_of_paper(x, y) && adjacent[y * width + x] < self.rule.threshold {
+                    self.remove_and_update(x, y, adjacent, &mut newly_accessible);
+                    sweep.push((x, y));
                 }
             }
+            newly_accessible.clear();
 
-            if original_count == count {
-                break
+            if sweep.is_empty() {
+                break;
             }
+            report.removals.push(sweep);
         }
 
-        count
+        report
+    }
+
+    /// Removes the roll at (x, y) and lowers the adjacent count of every roll that has it as a
+    /// neighbour, adding the rolls that just became accessible to `newly_accessible`
+    fn remove_and_update(
+        &mut self,
+        x: usize,
+        y: usize,
+        adjacent: &mut [usize],
+        newly_accessible: &mut Vec<(usize, usize)>,
+    ) {
+        let width = self.width();
+        self.remove_paper(x, y);
+
+        // the slots that count this one as a neighbour are at the opposite offsets,
+        // which only differs from the neighbours themselves for custom neighbourhoods
+        for &(dx, dy) in self.rule.neighbourhood.offsets() {
+            let (nx, ny) = match self.neighbour(x, y, (-dx, -dy)) {
+                Some(position) => position,
+                None => continue,
+            };
+            if !self.is_roll_of_paper(nx, ny) {
+                continue;
+            }
+
+            let idx = ny * width + nx;
+            adjacent[idx] -= 1;
+            if adjacent[idx] + 1 == self.rule.threshold {
+                newly_accessible.push((nx, ny));
+            }
+        }
+    }
+
+    /// Text of the grid, one string per row
+    fn to_chars(&self) -> Vec<Vec<char>> {
+        self.rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|slot| if slot.is_roll_of_paper() { '@' } else { '.' })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Replays a cleanup of this grid, one frame per round with that round's removals marked `x`
+    pub fn replay_frames(&self, report: &CleanupReport) -> Vec<String> {
+        let mut chars = self.to_chars();
+        let to_text = |chars: &Vec<Vec<char>>| -> String {
+            chars
+                .iter()
+                .map(|row| row.iter().collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let mut frames = vec![format!("Start\n{}", to_text(&chars))];
+        for (round, removals) in report.removals.iter().enumerate() {
+            for &(x, y) in removals.iter() {
+                chars[y][x] = 'x';
+            }
+            frames.push(format!(
+                "Round {}: removed {}\n{}",
+                round + 1,
+                removals.len(),
+                to_text(&chars)
+            ));
+
+            for &(x, y) in removals.iter() {
+                chars[y][x] = '.';
+            }
+        }
+
+        frames
+    }
+
+    /// The grid with every removed roll replaced by the round it was removed in. Up to
+    /// `MAX_LABELLED_ROUNDS` rounds are labelled with one character per cell, more are written
+    /// as round numbers in right-aligned columns.
+    pub fn annotate_rounds(&self, report: &CleanupReport) -> String {
+        let labelled = report.rounds() <= MAX_LABELLED_ROUNDS;
+        let mut cells: Vec<Vec<String>> = self
+            .to_chars()
+            .iter()
+            .map(|row| row.iter().map(|c| c.to_string()).collect())
+            .collect();
+        for (round, removals) in report.removals.iter().enumerate() {
+            for &(x, y) in removals.iter() {
+                cells[y][x] = if labelled {
+                    round_label(round + 1).to_string()
+                } else {
+                    (round + 1).to_string()
+                };
+            }
+        }
+
+        let width = report.rounds().to_string().len();
+        cells
+            .iter()
+            .map(|row| {
+                if labelled {
+                    return row.concat();
+                }
+                row.iter()
+                    .map(|cell| format!("{:>width$}", cell, width = width))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
     }
 
     fn remove_paper(&mut self, x : usize, y : usize) {
@@ -163,6 +479,7 @@ impl Grid {
 
 pub struct Day4 {
     grid: Grid,
+    cleanup_report: Option<CleanupReport>,
 }
 
 impl Solution for Day4 {
@@ -172,19 +489,53 @@ impl Solution for Day4 {
     }
 
     fn part2(&mut self) -> String {
-        let accessible_rolls = self.grid.cleanup();
-        accessible_rolls.to_string()
+        let report = self.grid.clone().cleanup(SweepOrder::Simultaneous);
+        let total = report.total();
+        self.cleanup_report = Some(report);
+        total.to_string()
+    }
+
+    fn diagnostics(&self) -> Vec<String> {
+        match &self.cleanup_report {
+            Some(report) => vec![format!(
+                "Cleanup rounds: {}, removed per round: {:?}",
+                report.rounds(),
+                report.removed_per_round()
+            )],
+            None => vec![],
+        }
+    }
+
+    fn views(&self) -> Vec<&'static str> {
+        vec!["frames", "rounds"]
+    }
+
+    /// Views are `frames` or `rounds`, optionally followed by a sweep order such as
+    /// `rounds:row-major`
+    fn visualize(&mut self, view: &str) -> Option<Vec<String>> {
+        let (kind, order) = view.split_once(':').unwrap_or((view, "simultaneous"));
+        let order: SweepOrder = order.parse().ok()?;
+        let report = self.grid.clone().cleanup(order);
+
+        match kind {
+            "frames" => Some(self.grid.replay_frames(&report)),
+            "rounds" => Some(vec![self.grid.annotate_rounds(&report)]),
+            _ => None,
+        }
     }
 }
 
 impl SolutionFactory for Day4 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
 
         let grid = Grid::from_file(&input);
 
-        Box::new(Day4 { grid })
+        Box::new(Day4 {
+            grid,
+            cleanup_report: None,
+        })
     }
 }
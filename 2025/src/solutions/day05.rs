@@ -1,7 +1,12 @@
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+use std::io::BufRead;
+
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
 
 type IngredientID = u64;
 
+const QUERY_USAGE: &str = "Queries: is-fresh <id>, count-fresh <a>-<b>, nearest-fresh <id>, gaps, \
+covered-by <id>, spans, redundant";
+
 #[derive(Clone, Copy)]
 struct IDRange {
     start: IngredientID,
@@ -18,6 +23,12 @@ impl IDRange {
     }
 }
 
+impl std::fmt::Display for IDRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
 impl From<&str> for IDRange {
     fn from(value: &str) -> Self {
         let mut split_items = value.split('-');
@@ -51,28 +62,82 @@ impl From<&str> for IDRange {
     }
 }
 
+/// A range as it was listed in the input
+#[derive(Clone, Copy)]
+struct SourceRange {
+    /// Line number in the input file, starting at 1
+    line: usize,
+    range: IDRange,
+}
+
+impl std::fmt::Display for SourceRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.range)
+    }
+}
+
+/// A source range that every other range together already covers
+struct RedundantRange {
+    source: SourceRange,
+    /// Lines of the other ranges that overlap it
+    covered_by: Vec<usize>,
+}
+
+/// Sorted, non-overlapping ranges with nothing else attached
 #[derive(Default)]
-struct InventoryManagementSystem {
+struct IntervalSet {
     ranges: Vec<IDRange>,
-    available_ids: Vec<IngredientID>,
 }
 
-impl InventoryManagementSystem {
-    fn add_range(&mut self, n_range: IDRange) {
-        let insert_idx = self.ranges.iter().position(|existing_range| {
-            if n_range.start < existing_range.start {
-                true
-            } else {
-                false
-            }
-        });
+impl IntervalSet {
+    /// Merges overlapping ranges. Ranges that only touch, such as 1-3 and 4-5, stay apart.
+    fn from_ranges(mut ranges: Vec<IDRange>) -> IntervalSet {
+        ranges.sort_by_key(|range| range.start);
 
-        match insert_idx {
-            Some(idx) => self.ranges.insert(idx, n_range),
-            None => self.ranges.push(n_range),
+        let mut merged: Vec<IDRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
         }
+
+        merged.shrink_to_fit();
+        IntervalSet { ranges: merged }
+    }
+
+    /// Index of the first range that does not end before `id`
+    fn first_range_ending_at_or_after(&self, id: IngredientID) -> usize {
+        self.ranges.partition_point(|range| range.end < id)
+    }
+
+    fn contains(&self, id: IngredientID) -> bool {
+        let idx = self.first_range_ending_at_or_after(id);
+        self.ranges.get(idx).is_some_and(|range| range.contains(id))
+    }
+
+    /// Whether any ID of `range` is in the set
+    fn overlaps(&self, range: &IDRange) -> bool {
+        let idx = self.first_range_ending_at_or_after(range.start);
+        self.ranges.get(idx).is_some_and(|found| found.start <= range.end)
+    }
+
+    fn total_width(&self) -> u64 {
+        self.ranges.iter().map(|range| range.width()).sum()
     }
+}
+
+#[derive(Default)]
+struct InventoryManagementSystem {
+    /// The source ranges merged, once pruned
+    fresh: IntervalSet,
+    /// For each range in `fresh`, the input lines of the source ranges merged into it
+    range_lines: Vec<Vec<usize>>,
+    source_ranges: Vec<SourceRange>,
+    available_ids: Vec<IngredientID>,
+}
 
+impl InventoryManagementSystem {
     fn add_ingredient(&mut self, ingredient: IngredientID) {
         self.available_ids.push(ingredient);
     }
@@ -80,7 +145,7 @@ impl InventoryManagementSystem {
     fn from_file(data: &str) -> InventoryManagementSystem {
         let mut ims = InventoryManagementSystem::default();
         let mut parsing_ranges = true;
-        data.lines().map(|line| line.trim()).for_each(|line| {
+        data.lines().map(|line| line.trim()).enumerate().for_each(|(idx, line)| {
             if line.is_empty() {
                 assert!(parsing_ranges, "Got multiple empty lines in file");
                 parsing_ranges = false;
@@ -89,7 +154,8 @@ impl InventoryManagementSystem {
 
             if parsing_ranges {
                 let range = IDRange::from(line);
-                ims.add_range(range);
+                let line = idx + 1;
+                ims.source_ranges.push(SourceRange { line, range });
             } else {
                 let ingredient: IngredientID = line.parse().unwrap();
                 ims.add_ingredient(ingredient);
@@ -104,58 +170,270 @@ impl InventoryManagementSystem {
     fn fresh_ingredients(&self) -> usize {
         self.available_ids
             .iter()
-            .filter(|&&id| {
-                for range in self.ranges.iter() {
-                    if range.contains(id) {
-                        return true;
-                    }
+            .filter(|&&id| self.is_fresh(id))
+            .count()
+    }
+
+    fn is_fresh(&self, id: IngredientID) -> bool {
+        self.fresh.contains(id)
+    }
+
+    /// How many IDs in `query` are fresh
+    fn count_fresh(&self, query: IDRange) -> u64 {
+        let idx = self.fresh.first_range_ending_at_or_after(query.start);
+        self.fresh.ranges[idx..]
+            .iter()
+            .take_while(|range| range.start <= query.end)
+            .map(|range| range.end.min(query.end) - range.start.max(query.start) + 1)
+            .sum()
+    }
+
+    /// The fresh ID closest to `id` (the lower one on a tie), if any ID is fresh
+    fn nearest_fresh(&self, id: IngredientID) -> Option<IngredientID> {
+        let idx = self.fresh.first_range_ending_at_or_after(id);
+        let below = idx.checked_sub(1).map(|prev| self.fresh.ranges[prev].end);
+        let above = self.fresh.ranges.get(idx).map(|range| range.start.max(id));
+
+        match (below, above) {
+            (Some(below), Some(above)) => {
+                if id - below <= above - id {
+                    Some(below)
+                } else {
+                    Some(above)
                 }
+            }
+            (below, above) => below.or(above),
+        }
+    }
 
-                false
+    /// Spoiled IDs between the pruned ranges. Ranges that touch are kept apart by `prune`, but
+    /// have no gap between them.
+    fn gaps(&self) -> Vec<IDRange> {
+        self.fresh
+            .ranges
+            .windows(2)
+            .filter(|pair| pair[0].end + 1 < pair[1].start)
+            .map(|pair| IDRange {
+                start: pair[0].end + 1,
+                end: pair[1].start - 1,
             })
-            .count()
+            .collect()
     }
 
-    /// Removes all overlapping ranges into a consolidated larger range
-    fn prune(&mut self) {
-        let mut current_i = 0;
-        while current_i < self.ranges.len() {
-            let mut absorbant_range = self.ranges[current_i];
-            let mut indexes_to_remove = vec![];
-
-            for (j, other_range) in self.ranges.iter().skip(current_i + 1).enumerate() {
-                if other_range.start <= absorbant_range.end {
-                    absorbant_range.end = absorbant_range.end.max(other_range.end);
-                    let actual_index = j + current_i + 1;
-                    indexes_to_remove.push(actual_index);
-                } else {
-                    break;
+    /// The ranges from the input that contain `id`
+    fn covering_ranges(&self, id: IngredientID) -> Vec<SourceRange> {
+        self.source_ranges
+            .iter()
+            .filter(|source| source.range.contains(id))
+            .copied()
+            .collect()
+    }
+
+    /// IDs listed by exactly one source range, found in one sweep over where ranges start and
+    /// end
+    fn singly_listed(&self) -> IntervalSet {
+        // +1 where a range starts and -1 after it ends, a range ending at the last ID never does
+        let mut changes: Vec<(IngredientID, i32)> = vec![];
+        for source in self.source_ranges.iter() {
+            changes.push((source.range.start, 1));
+            if let Some(after) = source.range.end.checked_add(1) {
+                changes.push((after, -1));
+            }
+        }
+        changes.sort_unstable();
+
+        let mut singly_listed = vec![];
+        let mut listed_by = 0;
+        for (idx, &(id, change)) in changes.iter().enumerate() {
+            listed_by += change;
+
+            // the count holds from here until the next change, once every change here is made
+            let end = match changes.get(idx + 1) {
+                Some(&(next, _)) if next == id => continue,
+                Some(&(next, _)) => next - 1,
+                None => IngredientID::MAX,
+            };
+            if listed_by == 1 {
+                singly_listed.push(IDRange { start: id, end });
+            }
+        }
+
+        IntervalSet::from_ranges(singly_listed)
+    }
+
+    /// Source ranges whose IDs are all listed by other ranges as well
+    ///
+    /// Identical ranges each cover the other, so both are reported
+    fn redundant_sources(&self) -> Vec<SourceRange> {
+        let singly_listed = self.singly_listed();
+        self.source_ranges
+            .iter()
+            .filter(|source| !singly_listed.overlaps(&source.range))
+            .copied()
+            .collect()
+    }
+
+    /// Redundant source ranges, each with the other ranges that overlap it
+    fn redundant_ranges(&self) -> Vec<RedundantRange> {
+        self.redundant_sources()
+            .into_iter()
+            .map(|source| {
+                let mut covered_by: Vec<usize> = self
+                    .source_ranges
+                    .iter()
+                    .filter(|other| {
+                        other.line != source.line
+                            && other.range.start <= source.range.end
+                            && other.range.end >= source.range.start
+                    })
+                    .map(|other| other.line)
+                    .collect();
+                covered_by.sort();
+                RedundantRange { source, covered_by }
+            })
+            .collect()
+    }
+
+    /// Answers one query line, see `QUERY_USAGE` for the commands
+    fn query(&self, command: &str) -> Result<String, String> {
+        let (name, arg) = command
+            .trim()
+            .split_once(' ')
+            .map(|(name, arg)| (name, arg.trim()))
+            .unwrap_or((command.trim(), ""));
+        let parse_id = |arg: &str| -> Result<IngredientID, String> {
+            arg.parse().map_err(|_| format!("Invalid ingredient ID: {}", arg))
+        };
+        let join = |items: Vec<String>, separator: &str| -> String {
+            if items.is_empty() {
+                return String::from("none");
+            }
+            items.join(separator)
+        };
+
+        match name {
+            "is-fresh" => Ok(self.is_fresh(parse_id(arg)?).to_string()),
+            "count-fresh" => {
+                let (start, end) = arg
+                    .split_once('-')
+                    .ok_or(format!("Expected a range like 3-5, got: {}", arg))?;
+                let (start, end) = (parse_id(start.trim())?, parse_id(end.trim())?);
+                if start > end {
+                    return Err(format!("Range start is after its end: {}", arg));
                 }
+                Ok(self.count_fresh(IDRange { start, end }).to_string())
             }
+            "nearest-fresh" => match self.nearest_fresh(parse_id(arg)?) {
+                Some(id) => Ok(id.to_string()),
+                None => Err(String::from("No fresh ingredients")),
+            },
+            "gaps" => Ok(join(
+                self.gaps().iter().map(|gap| gap.to_string()).collect(),
+                " ",
+            )),
+            "covered-by" => Ok(join(
+                self.covering_ranges(parse_id(arg)?)
+                    .iter()
+                    .map(|source| source.to_string())
+                    .collect(),
+                "\n",
+            )),
+            "spans" => Ok(join(
+                self.fresh
+                    .ranges
+                    .iter()
+                    .zip(self.range_lines.iter())
+                    .map(|(range, lines)| format!("{} from lines {:?}", range, lines))
+                    .collect(),
+                "\n",
+            )),
+            "redundant" => Ok(join(
+                self.redundant_ranges()
+                    .iter()
+                    .map(|redundant| {
+                        format!(
+                            "{} is covered by lines {:?}",
+                            redundant.source, redundant.covered_by
+                        )
+                    })
+                    .collect(),
+                "\n",
+            )),
+            _ => Err(format!("Unknown query: {}\n{}", command.trim(), QUERY_USAGE)),
+        }
+    }
+
+    /// Removes all overlapping ranges into a consolidated larger range, keeping track of which
+    /// input lines each consolidated range came from
+    fn prune(&mut self) {
+        let ranges = self.source_ranges.iter().map(|source| source.range).collect();
+        self.fresh = IntervalSet::from_ranges(ranges);
 
-            indexes_to_remove.iter().rev().for_each(|index| {
-                self.ranges.remove(*index);
-            });
-            self.ranges[current_i] = absorbant_range;
-            current_i += 1;
+        self.range_lines = vec![vec![]; self.fresh.ranges.len()];
+        for source in self.source_ranges.iter() {
+            let idx = self.fresh.first_range_ending_at_or_after(source.range.start);
+            self.range_lines[idx].push(source.line);
         }
 
         // sanity check to verify no overlapping
-        for (i, range) in self.ranges.iter().take(self.ranges.len() - 1).enumerate() {
-            for other_range in self.ranges.iter().skip(i + 1) {
-                assert!(
-                    range.end < other_range.start,
-                    "Got overlapping ranges after prune"
-                );
-            }
+        for pair in self.fresh.ranges.windows(2) {
+            assert!(
+                pair[0].end < pair[1].start,
+                "Got overlapping ranges after prune"
+            );
         }
     }
 
     fn actual_fresh_ingredients_according_to_ranges(&self) -> u64 {
-        self.ranges.iter().map(|range| range.width()).sum()
+        self.fresh.total_width()
     }
 }
 
+/// Solves both parts while reading the inventory one line at a time
+///
+/// The ranges are merged into an `IntervalSet` before any IDs are read, then each ID is checked
+/// and dropped, so memory only grows with the number of ranges
+pub fn stream_inventory(reader: &mut dyn BufRead) -> (String, String) {
+    let mut line = String::new();
+    let mut read_line = |line: &mut String| -> bool {
+        line.clear();
+        reader
+            .read_line(line)
+            .expect("Failed to read inventory from stream")
+            > 0
+    };
+
+    let mut ranges = vec![];
+    while read_line(&mut line) {
+        let range_str = line.trim();
+        if range_str.is_empty() {
+            break;
+        }
+        ranges.push(IDRange::from(range_str));
+    }
+    let fresh_ranges = IntervalSet::from_ranges(ranges);
+
+    let mut fresh_ingredients: u64 = 0;
+    while read_line(&mut line) {
+        let id_str = line.trim();
+        if id_str.is_empty() {
+            continue;
+        }
+
+        let id: IngredientID = id_str
+            .parse()
+            .unwrap_or_else(|_| panic!("Invalid ingredient ID: {}", id_str));
+        if fresh_ranges.contains(id) {
+            fresh_ingredients += 1;
+        }
+    }
+
+    (
+        fresh_ingredients.to_string(),
+        fresh_ranges.total_width().to_string(),
+    )
+}
+
 pub struct Day5 {
     ims: InventoryManagementSystem,
 }
@@ -170,12 +448,25 @@ impl Solution for Day5 {
         let fresh_ingredients = self.ims.actual_fresh_ingredients_according_to_ranges();
         fresh_ingredients.to_string()
     }
+
+    fn diagnostics(&self) -> Vec<String> {
+        vec![format!(
+            "Merged {} source ranges into {}, {} fully redundant",
+            self.ims.source_ranges.len(),
+            self.ims.fresh.ranges.len(),
+            self.ims.redundant_sources().len()
+        )]
+    }
+
+    fn query(&mut self, command: &str) -> Result<String, String> {
+        self.ims.query(command)
+    }
 }
 
 impl SolutionFactory for Day5 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
 
         let ims = InventoryManagementSystem::from_file(&input);
@@ -1,202 +1,868 @@
-use std::mem;
+use std::{ops::Range, str::FromStr};
 
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
+
+/// Worksheet values are exact integers, wide enough that overflow only happens on purpose
+type Value = i128;
 
 #[derive(Clone, Copy)]
 enum Operation {
     Add,
+    Subtract,
     Multiply,
+    Divide,
+    Remainder,
+    Power,
+    Min,
+    Max,
+}
+
+impl Operation {
+    fn apply(&self, left: Value, right: Value) -> Result<Value, EvalError> {
+        match self {
+            Operation::Add => left.checked_add(right).ok_or(EvalError::Overflow),
+            Operation::Subtract => left.checked_sub(right).ok_or(EvalError::Overflow),
+            Operation::Multiply => left.checked_mul(right).ok_or(EvalError::Overflow),
+            Operation::Divide | Operation::Remainder if right == 0 => {
+                Err(EvalError::DivisionByZero)
+            }
+            // values are whole numbers, so a division with a remainder has no exact result
+            Operation::Divide => match left.checked_rem(right) {
+                Some(0) => left.checked_div(right).ok_or(EvalError::Overflow),
+                Some(_) => Err(EvalError::InexactDivision),
+                None => Err(EvalError::Overflow),
+            },
+            Operation::Remainder => left.checked_rem(right).ok_or(EvalError::Overflow),
+            Operation::Power => {
+                let exponent: u32 = right
+                    .try_into()
+                    .map_err(|_| EvalError::InvalidExponent(right))?;
+                left.checked_pow(exponent).ok_or(EvalError::Overflow)
+            }
+            Operation::Min => Ok(left.min(right)),
+            Operation::Max => Ok(left.max(right)),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Operation::Add => "+",
+            Operation::Subtract => "-",
+            Operation::Multiply => "*",
+            Operation::Divide => "/",
+            Operation::Remainder => "%",
+            Operation::Power => "^",
+            Operation::Min => "min",
+            Operation::Max => "max",
+        }
+    }
+
+    /// Binding strength in an infix expression, higher binds tighter
+    fn precedence(&self) -> u8 {
+        match self {
+            Operation::Min | Operation::Max => 1,
+            Operation::Add | Operation::Subtract => 2,
+            Operation::Multiply | Operation::Divide | Operation::Remainder => 3,
+            Operation::Power => 4,
+        }
+    }
+}
+
+impl Operation {
+    fn parse(value: &str) -> Option<Operation> {
+        match value {
+            "+" => Some(Operation::Add),
+            "-" => Some(Operation::Subtract),
+            "*" => Some(Operation::Multiply),
+            "/" => Some(Operation::Divide),
+            "%" => Some(Operation::Remainder),
+            "^" => Some(Operation::Power),
+            "min" => Some(Operation::Min),
+            "max" => Some(Operation::Max),
+            _ => None,
+        }
+    }
 }
 
 impl From<&str> for Operation {
     fn from(value: &str) -> Self {
-        assert!(
-            value.len() == 1,
-            "Operator string must be a single char, got: {}",
-            value
-        );
-        match value.chars().nth(0).unwrap() {
-            '+' => Operation::Add,
-            '*' => Operation::Multiply,
-            _ => panic!("Got undefined operator: {}", value),
+        Operation::parse(value).unwrap_or_else(|| panic!("Got undefined operator: {}", value))
+    }
+}
+
+#[derive(Debug)]
+enum EvalError {
+    DivisionByZero,
+
+    /// Division that leaves a remainder
+    InexactDivision,
+    Overflow,
+    /// Exponent was negative or too large to raise anything but 0 and 1 to
+    InvalidExponent(Value),
+}
+
+impl std::fmt::Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EvalError::DivisionByZero => write!(f, "division by zero"),
+            EvalError::InexactDivision => write!(f, "inexact division"),
+            EvalError::Overflow => write!(f, "overflow"),
+            EvalError::InvalidExponent(exponent) => write!(f, "invalid exponent {}", exponent),
         }
     }
 }
 
-struct Equation {
-    nums: Vec<u64>,
-    operation: Operation,
+/// A worksheet cell, either a plain number or a parenthesised sub-expression like `(2^10-1)`
+#[derive(Clone)]
+enum Expr {
+    Number(Value),
+    Binary(Operation, Box<Expr>, Box<Expr>),
 }
 
-impl Equation {
-    pub fn cephalopods_format_1(data: &str) -> Vec<Equation> {
-        let lines: Vec<&str> = data.lines().filter(|line| !line.is_empty()).collect();
+/// Writes the expression in infix form, only adding the parentheses it needs
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Number(number) => write!(f, "{}", number),
+            Expr::Binary(operation, left, right) => {
+                let right_associative = matches!(operation, Operation::Power);
+                let needs_parens = |child: &Expr, is_left: bool| match child {
+                    Expr::Number(_) => false,
+                    Expr::Binary(inner, _, _) => {
+                        inner.precedence() < operation.precedence()
+                            || (inner.precedence() == operation.precedence()
+                                && is_left == right_associative)
+                    }
+                };
 
-        // get all the number rows
-        let rows: Vec<Vec<u64>> = lines
-            .iter()
-            .take(lines.len() - 1)
-            .map(|line| {
-                let nums: Vec<u64> = line
-                    .split(" ")
-                    .filter_map(|number| {
-                        let number = number.trim();
-                        if number.is_empty() {
-                            return None;
-                        }
-
-                        let number: u64 = number
-                            .parse()
-                            .expect(&format!("Invalid number: {}", number));
-                        Some(number)
-                    })
-                    .collect();
-                nums
-            })
+                for (child, is_left) in [(left, true), (right, false)] {
+                    if !is_left {
+                        write!(f, " {} ", operation.symbol())?;
+                    }
+                    if needs_parens(child, is_left) {
+                        write!(f, "({})", child)?;
+                    } else {
+                        write!(f, "{}", child)?;
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Number(Value),
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+impl Expr {
+    /// Parses an infix expression, `^` is right associative and the rest are left associative
+    pub fn parse(text: &str) -> Result<Expr, String> {
+        let tokens = Expr::tokenize(text)?;
+        let mut pos = 0;
+        let expr = Expr::parse_binary(&tokens, &mut pos, 0)?;
+        if pos != tokens.len() {
+            return Err(format!("Unexpected trailing input in expression: {}", text));
+        }
+
+        Ok(expr)
+    }
+
+    fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+        const OPERATORS: [&str; 8] = ["+", "-", "*", "/", "%", "^", "min", "max"];
+
+        let mut tokens = vec![];
+        let mut rest = text.trim_start();
+        while !rest.is_empty() {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits > 0 {
+                let number = rest[..digits]
+                    .parse()
+                    .map_err(|_| format!("Number too large: {}", &rest[..digits]))?;
+                tokens.push(Token::Number(number));
+                rest = &rest[digits..];
+            } else if let Some(after) = rest.strip_prefix('(') {
+                tokens.push(Token::Open);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(')') {
+                tokens.push(Token::Close);
+                rest = after;
+            } else if let Some(operator) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+                tokens.push(Token::Operator(operator));
+                rest = &rest[operator.len()..];
+            } else {
+                return Err(format!("Unexpected character in expression: {}", text));
+            }
+
+            rest = rest.trim_start();
+        }
+
+        Ok(tokens)
+    }
+
+    /// Precedence climbing, only operators binding at least as tight as `min_precedence` are
+    /// consumed at this level
+    fn parse_binary(tokens: &[Token], pos: &mut usize, min_precedence: u8) -> Result<Expr, String> {
+        let mut left = Expr::parse_operand(tokens, pos)?;
+
+        while let Some(Token::Operator(symbol)) = tokens.get(*pos) {
+            let operation = Operation::from(*symbol);
+            let precedence = operation.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+
+            *pos += 1;
+            let next_min = match operation {
+                Operation::Power => precedence,
+                _ => precedence + 1,
+            };
+            let right = Expr::parse_binary(tokens, pos, next_min)?;
+            left = Expr::Binary(operation, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_operand(tokens: &[Token], pos: &mut usize) -> Result<Expr, String> {
+        let token = tokens.get(*pos).ok_or("Expression ended early")?;
+        *pos += 1;
+
+        match token {
+            Token::Number(number) => Ok(Expr::Number(*number)),
+            Token::Open => {
+                let inner = Expr::parse_binary(tokens, pos, 0)?;
+                if tokens.get(*pos) != Some(&Token::Close) {
+                    return Err(String::from("Missing closing parenthesis"));
+                }
+                *pos += 1;
+                Ok(inner)
+            }
+            Token::Operator(symbol) => Err(format!("Expected a number, got: {}", symbol)),
+            Token::Close => Err(String::from("Unexpected closing parenthesis")),
+        }
+    }
+
+    fn eval(&self) -> Result<Value, EvalError> {
+        match self {
+            Expr::Number(number) => Ok(*number),
+            Expr::Binary(operation, left, right) => operation.apply(left.eval()?, right.eval()?),
+        }
+    }
+}
+
+/// Line and column in the worksheet text, both starting at 1
+#[derive(Clone, Copy, Debug)]
+struct Position {
+    line: usize,
+    column: usize,
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// Reasons a worksheet, or one of its problems, cannot be read
+#[derive(Clone, Debug)]
+enum WorksheetError {
+    /// Fewer than one number row and the operator row
+    Empty,
+    InvalidNumber {
+        position: Position,
+        text: String,
+        reason: String,
+    },
+    /// A number row has nothing in this problem's columns
+    EmptyCell {
+        position: Position,
+        problem: usize,
+    },
+    InvalidOperator {
+        position: Position,
+        text: String,
+    },
+    MissingOperator {
+        position: Position,
+        problem: usize,
+    },
+    /// A second operator under the same problem, usually a missing gutter between two problems
+    ExtraOperator {
+        position: Position,
+        problem: usize,
+    },
+}
+
+impl std::fmt::Display for WorksheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorksheetError::Empty => write!(f, "worksheet needs number rows and an operator row"),
+            WorksheetError::InvalidNumber {
+                position,
+                text,
+                reason,
+            } => write!(f, "{}: invalid number {:?} ({})", position, text, reason),
+            WorksheetError::EmptyCell { position, problem } => {
+                write!(f, "{}: no number for problem {}", position, problem)
+            }
+            WorksheetError::InvalidOperator { position, text } => {
+                write!(f, "{}: invalid operator {:?}", position, text)
+            }
+            WorksheetError::MissingOperator { position, problem } => {
+                write!(f, "{}: no operator for problem {}", position, problem)
+            }
+            WorksheetError::ExtraOperator { position, problem } => {
+                write!(
+                    f,
+                    "{}: more than one operator for problem {}",
+                    position, problem
+                )
+            }
+        }
+    }
+}
+
+/// Layout problems that do not stop a worksheet from being read
+enum WorksheetWarning {
+    /// Line is shorter or longer than the widest line
+    Ragged {
+        line: usize,
+        width: usize,
+        expected: usize,
+    },
+    /// Whitespace after the last problem, starting at `position`
+    TrailingWhitespace { position: Position },
+}
+
+impl std::fmt::Display for WorksheetWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WorksheetWarning::Ragged {
+                line,
+                width,
+                expected,
+            } => write!(f, "line {} is {} wide, expected {}", line, width, expected),
+            WorksheetWarning::TrailingWhitespace { position } => {
+                write!(f, "{}: trailing whitespace", position)
+            }
+        }
+    }
+}
+
+/// Which end of a problem its character columns are read from, also the order of the problems
+#[derive(Clone, Copy, Default)]
+enum ColumnOrder {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+/// Which end of a character column its digits are read from
+#[derive(Clone, Copy, Default)]
+enum DigitOrder {
+    #[default]
+    TopDown,
+    BottomUp,
+}
+
+/// How a worksheet is read, the default is how the puzzle reads it
+#[derive(Clone, Copy, Default)]
+struct ReadingOptions {
+    column_order: ColumnOrder,
+    digit_order: DigitOrder,
+    /// Worksheet is written with its rows and columns swapped, operators in the last column
+    transposed: bool,
+}
+
+/// Parses comma separated options, e.g. `right-to-left,bottom-up,transposed`
+impl FromStr for ReadingOptions {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut options = ReadingOptions::default();
+        for option in value.split(',').map(|option| option.trim()) {
+            match option {
+                "" => {}
+                "left-to-right" => options.column_order = ColumnOrder::LeftToRight,
+                "right-to-left" => options.column_order = ColumnOrder::RightToLeft,
+                "top-down" => options.digit_order = DigitOrder::TopDown,
+                "bottom-up" => options.digit_order = DigitOrder::BottomUp,
+                "transposed" => options.transposed = true,
+                _ => return Err(format!("Unknown reading option: {}", option)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// One problem of the worksheet, the columns between two whitespace gutters
+struct ProblemBlock {
+    /// Position of the problem counting from the left, starting at 1
+    problem: usize,
+    /// Character columns the block spans, starting at 0
+    columns: Range<usize>,
+    /// The block's text on each line, operator row last
+    raw: Vec<String>,
+    operation: Result<Operation, WorksheetError>,
+    /// Numbers read across each row (the first format)
+    row_reading: Result<Vec<Expr>, WorksheetError>,
+    /// Numbers read down each character column (the second format)
+    column_reading: Result<Vec<Expr>, WorksheetError>,
+}
+
+struct Worksheet {
+    /// Problems from left to right
+    blocks: Vec<ProblemBlock>,
+    warnings: Vec<WorksheetWarning>,
+    options: ReadingOptions,
+}
+
+impl Worksheet {
+    /// Splits the worksheet into problems at the character columns that are blank on every
+    /// line, the last non-empty line being the operator row
+    pub fn parse(data: &str, options: ReadingOptions) -> Result<Worksheet, WorksheetError> {
+        let lines: Vec<(usize, Vec<char>)> = if options.transposed {
+            Worksheet::transpose(data)
+        } else {
+            data.lines()
+                .enumerate()
+                .map(|(idx, line)| (idx + 1, line.chars().collect()))
+                .collect()
+        };
+        let lines: Vec<(usize, Vec<char>)> = lines
+            .into_iter()
+            .filter(|(_, line)| line.iter().any(|c| !c.is_whitespace()))
             .collect();
+        if lines.len() < 2 {
+            return Err(WorksheetError::Empty);
+        }
 
-        // parse the operator row
-        let operators: Vec<Operation> = lines
-            .last()
-            .unwrap()
-            .split(" ")
-            .filter_map(|operator| {
-                let operator = operator.trim();
-                if operator.is_empty() {
-                    return None;
+        let width = lines.iter().map(|(_, line)| line.len()).max().unwrap();
+        let is_blank = |line: &[char], col: usize| line.get(col).is_none_or(|c| c.is_whitespace());
+        let gutters: Vec<bool> = (0..width)
+            .map(|col| lines.iter().all(|(_, line)| is_blank(line, col)))
+            .collect();
+
+        let mut block_columns = vec![];
+        let mut block_start = None;
+        for col in 0..=width {
+            let is_gutter = gutters.get(col).copied().unwrap_or(true);
+            match (block_start, is_gutter) {
+                (None, false) => block_start = Some(col),
+                (Some(start), true) => {
+                    block_columns.push(start..col);
+                    block_start = None;
                 }
+                _ => {}
+            }
+        }
 
-                Some(Operation::from(operator))
-            })
+        // padding added while transposing is not worth warning about
+        let content_end = block_columns.last().map_or(0, |columns| columns.end);
+        let mut warnings = vec![];
+        for (line_number, line) in lines.iter().filter(|_| !options.transposed) {
+            if line.len() != width {
+                warnings.push(WorksheetWarning::Ragged {
+                    line: *line_number,
+                    width: line.len(),
+                    expected: width,
+                });
+            }
+            if line.len() > content_end {
+                warnings.push(WorksheetWarning::TrailingWhitespace {
+                    position: Position {
+                        line: *line_number,
+                        column: content_end + 1,
+                    },
+                });
+            }
+        }
+
+        let blocks = block_columns
+            .into_iter()
+            .enumerate()
+            .map(|(idx, columns)| ProblemBlock::read(idx + 1, columns, &lines, &options))
             .collect();
 
-        // sanity check that all rows are of equal size
-        let fixed_size = rows.get(0).unwrap().len();
-        rows.iter().skip(1).for_each(|row| {
-            assert_eq!(fixed_size, row.len());
-        });
-        assert_eq!(operators.len(), fixed_size);
+        Ok(Worksheet {
+            blocks,
+            warnings,
+            options,
+        })
+    }
 
-        let mut equations = Vec::with_capacity(fixed_size);
-        for col in 0..fixed_size {
-            let nums: Vec<u64> = rows.iter().map(|row| row[col]).collect();
+    /// Swaps rows and columns, padding short lines with spaces. Each returned line is numbered
+    /// by the column it came from.
+    fn transpose(data: &str) -> Vec<(usize, Vec<char>)> {
+        let rows: Vec<Vec<char>> = data.lines().map(|line| line.chars().collect()).collect();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
 
-            let operation = operators[col];
+        (0..width)
+            .map(|col| {
+                let line = rows
+                    .iter()
+                    .map(|row| row.get(col).copied().unwrap_or(' '))
+                    .collect();
+                (col + 1, line)
+            })
+            .collect()
+    }
 
-            equations.push(Equation { nums, operation })
+    /// Problems in the order the reading options visit them
+    fn ordered_blocks(&self) -> Vec<&ProblemBlock> {
+        let mut blocks: Vec<&ProblemBlock> = self.blocks.iter().collect();
+        if matches!(self.options.column_order, ColumnOrder::RightToLeft) {
+            blocks.reverse();
         }
-
-        equations
+        blocks
     }
+}
+
+impl ProblemBlock {
+    fn read(
+        problem: usize,
+        columns: Range<usize>,
+        lines: &[(usize, Vec<char>)],
+        options: &ReadingOptions,
+    ) -> ProblemBlock {
+        let raw: Vec<String> = lines
+            .iter()
+            .map(|(_, line)| {
+                let end = columns.end.min(line.len());
+                let start = columns.start.min(end);
+                line[start..end].iter().collect()
+            })
+            .collect();
+        let line_numbers: Vec<usize> = lines.iter().map(|(line, _)| *line).collect();
+        let operator_line = *line_numbers.last().unwrap();
+        // positions always point into the worksheet as it was written
+        let position = |line: usize, offset: usize| {
+            let column = columns.start + offset + 1;
+            if options.transposed {
+                Position {
+                    line: column,
+                    column: line,
+                }
+            } else {
+                Position { line, column }
+            }
+        };
+        let first_non_blank = |text: &str| text.chars().take_while(|c| c.is_whitespace()).count();
 
-    pub fn cephalopods_format_2(data: &str) -> Vec<Equation> {
-        let lines: Vec<&str> = data.lines().filter(|line| !line.is_empty()).collect();
+        // the operator row should hold exactly one operator
+        let operator_text = raw.last().unwrap();
+        let mut operators = operator_text.split_whitespace();
+        let operation = match (operators.next(), operators.next()) {
+            (None, _) => Err(WorksheetError::MissingOperator {
+                position: position(operator_line, 0),
+                problem,
+            }),
+            (Some(first), Some(extra)) => {
+                let first_end = operator_text.find(first).unwrap() + first.len();
+                let extra_start = first_end + operator_text[first_end..].find(extra).unwrap();
+                Err(WorksheetError::ExtraOperator {
+                    position: position(operator_line, operator_text[..extra_start].chars().count()),
+                    problem,
+                })
+            }
+            (Some(text), None) => {
+                Operation::parse(text).ok_or_else(|| WorksheetError::InvalidOperator {
+                    position: position(operator_line, first_non_blank(operator_text)),
+                    text: String::from(text),
+                })
+            }
+        };
 
-        // parse the operator row
-        let operators: Vec<Operation> = lines
-            .last()
-            .unwrap()
-            .split(" ")
-            .filter_map(|operator| {
-                let operator = operator.trim();
-                if operator.is_empty() {
-                    return None;
+        let number_rows = &raw[..raw.len() - 1];
+        let row_reading = number_rows
+            .iter()
+            .zip(line_numbers.iter())
+            .map(|(cell, &line)| {
+                let text = cell.trim();
+                if text.is_empty() {
+                    return Err(WorksheetError::EmptyCell {
+                        position: position(line, 0),
+                        problem,
+                    });
                 }
 
-                Some(Operation::from(operator))
+                Expr::parse(text).map_err(|reason| WorksheetError::InvalidNumber {
+                    position: position(line, first_non_blank(cell)),
+                    text: String::from(text),
+                    reason,
+                })
             })
             .collect();
 
-        // sanity check that all rows are of equal size
-        let fixed_size = lines.get(0).unwrap().len();
-        lines.iter().skip(1).for_each(|row| {
-            assert_eq!(fixed_size, row.len());
-        });
+        let offsets: Vec<usize> = match options.column_order {
+            ColumnOrder::LeftToRight => (0..columns.len()).collect(),
+            ColumnOrder::RightToLeft => (0..columns.len()).rev().collect(),
+        };
 
-        let mut equations = Vec::with_capacity(operators.len());
-        let mut nums = vec![];
-        let mut operator_idx = 0;
-        for col in 0..fixed_size {
-            let digits: String = lines
+        let mut column_reading = Ok(vec![]);
+        for offset in offsets {
+            let mut digits: Vec<(usize, char)> = number_rows
                 .iter()
-                .take(lines.len() - 1)
-                .filter_map(|line| {
-                    if let Some(digit) = line.chars().nth(col) {
-                        if !digit.is_whitespace() {
-                            return Some(digit);
-                        }
-                    }
-
-                    None
+                .zip(line_numbers.iter())
+                .filter_map(|(cell, &line)| {
+                    let c = cell.chars().nth(offset)?;
+                    (!c.is_whitespace()).then_some((line, c))
                 })
                 .collect();
 
+            // columns that are only wide for the operator have no number in them
             if digits.is_empty() {
-                let operation = operators[operator_idx];
-                operator_idx += 1;
-                let mut n_nums = vec![];
-                mem::swap(&mut nums, &mut n_nums);
-                equations.push(Equation {
-                    nums: n_nums,
-                    operation,
-                })
-            } else {
-                nums.push(digits.parse().unwrap());
+                continue;
+            }
+            if matches!(options.digit_order, DigitOrder::BottomUp) {
+                digits.reverse();
+            }
+
+            let text: String = digits.iter().map(|(_, c)| c).collect();
+            let number = match digits.iter().find(|(_, c)| !c.is_ascii_digit()) {
+                Some(&(line, c)) => Err((line, format!("{:?} is not a digit", c))),
+                None => text
+                    .parse()
+                    .map_err(|_| (digits[0].0, String::from("too large"))),
+            };
+
+            match (number, &mut column_reading) {
+                (Ok(number), Ok(nums)) => nums.push(Expr::Number(number)),
+                (Err((line, reason)), Ok(_)) => {
+                    column_reading = Err(WorksheetError::InvalidNumber {
+                        position: position(line, offset),
+                        text,
+                        reason,
+                    })
+                }
+                (_, Err(_)) => break,
             }
         }
 
-        if nums.len() > 0 {
-            let operation = operators[operator_idx];
-            equations.push(Equation { nums, operation })
+        if column_reading.as_ref().is_ok_and(|nums| nums.is_empty()) {
+            column_reading = Err(WorksheetError::EmptyCell {
+                position: position(line_numbers[0], 0),
+                problem,
+            });
         }
 
-        equations
+        ProblemBlock {
+            problem,
+            columns,
+            raw,
+            operation,
+            row_reading,
+            column_reading,
+        }
     }
+}
 
-    fn solve(&self) -> u64 {
-        match self.operation {
-            Operation::Add => self.nums.iter().sum(),
-            Operation::Multiply => self.nums.iter().product(),
-        }
+/// Turns a worksheet into its equations, one of the `cephalopods_format` functions
+type WorksheetReading = fn(&Worksheet) -> Result<Vec<Equation>, WorksheetError>;
+
+struct Equation {
+    nums: Vec<Expr>,
+    operation: Operation,
+}
+
+impl Equation {
+    /// Each row of a problem is one number
+    pub fn cephalopods_format_1(worksheet: &Worksheet) -> Result<Vec<Equation>, WorksheetError> {
+        worksheet
+            .ordered_blocks()
+            .into_iter()
+            .map(|block| {
+                Ok(Equation {
+                    nums: block.row_reading.clone()?,
+                    operation: block.operation.clone()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Each character column of a problem is one number, read top to bottom
+    pub fn cephalopods_format_2(worksheet: &Worksheet) -> Result<Vec<Equation>, WorksheetError> {
+        worksheet
+            .ordered_blocks()
+            .into_iter()
+            .map(|block| {
+                Ok(Equation {
+                    nums: block.column_reading.clone()?,
+                    operation: block.operation.clone()?,
+                })
+            })
+            .collect()
+    }
+
+    /// The operation applied across the numbers in reading order, e.g. `123 * 45 * 6`
+    fn to_expr(&self) -> Expr {
+        self.nums
+            .iter()
+            .cloned()
+            .reduce(|acc, num| Expr::Binary(self.operation, Box::new(acc), Box::new(num)))
+            .unwrap_or(Expr::Number(0))
+    }
+
+    fn solve(&self) -> Result<Value, EvalError> {
+        self.to_expr().eval()
+    }
+}
+
+/// Sum of every equation's answer, or a description of the first one that failed
+fn grand_total(equations: &[Equation]) -> String {
+    let total = equations
+        .iter()
+        .enumerate()
+        .try_fold(0 as Value, |total, (idx, equation)| {
+            equation
+                .solve()
+                .and_then(|answer| total.checked_add(answer).ok_or(EvalError::Overflow))
+                .map_err(|err| format!("Problem {} failed: {}", idx + 1, err))
+        });
+
+    match total {
+        Ok(total) => total.to_string(),
+        Err(err) => err,
     }
 }
 pub struct Day6 {
-    equations_part1: Vec<Equation>,
-    equations_part2: Vec<Equation>,
+    input: String,
+    worksheet: Result<Worksheet, WorksheetError>,
+}
+
+impl Day6 {
+    fn solve_format(&self, format: WorksheetReading) -> String {
+        match self
+            .worksheet
+            .as_ref()
+            .map_err(|err| err.clone())
+            .and_then(format)
+        {
+            Ok(equations) => grand_total(&equations),
+            Err(err) => format!("Invalid worksheet: {}", err),
+        }
+    }
+
+    /// Every problem block with its raw text and both of its readings
+    fn show_blocks(worksheet: &Worksheet) -> Vec<String> {
+        let show = |reading: &Result<Vec<Expr>, WorksheetError>| match reading {
+            Ok(nums) => nums
+                .iter()
+                .map(|num| num.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            Err(err) => format!("error: {}", err),
+        };
+
+        worksheet
+            .ordered_blocks()
+            .into_iter()
+            .map(|block| {
+                let operation = match &block.operation {
+                    Ok(operation) => operation.symbol().to_string(),
+                    Err(err) => format!("error: {}", err),
+                };
+                format!(
+                    "Problem {} (columns {}-{})\n{}\noperator: {}\nrows: {}\ncolumns: {}",
+                    block.problem,
+                    block.columns.start + 1,
+                    block.columns.end,
+                    block.raw.join("\n"),
+                    operation,
+                    show(&block.row_reading),
+                    show(&block.column_reading)
+                )
+            })
+            .collect()
+    }
+
+    /// Each part's equations written out in infix form next to their values
+    fn show_equations(worksheet: &Worksheet) -> Vec<String> {
+        let formats: [(&str, WorksheetReading); 2] = [
+            ("Part 1 (rows)", Equation::cephalopods_format_1),
+            ("Part 2 (columns)", Equation::cephalopods_format_2),
+        ];
+
+        formats
+            .iter()
+            .map(|(title, format)| {
+                let equations = match format(worksheet) {
+                    Ok(equations) => equations,
+                    Err(err) => return format!("{}\nInvalid worksheet: {}", title, err),
+                };
+
+                let mut lines = vec![title.to_string()];
+                for equation in equations.iter() {
+                    let value = match equation.solve() {
+                        Ok(value) => value.to_string(),
+                        Err(err) => format!("error: {}", err),
+                    };
+                    lines.push(format!("{} = {}", equation.to_expr(), value));
+                }
+                lines.push(format!("Total: {}", grand_total(&equations)));
+                lines.join("\n")
+            })
+            .collect()
+    }
 }
 
 impl Solution for Day6 {
     fn part1(&mut self) -> String {
-        let final_sum: u64 = self
-            .equations_part1
-            .iter()
-            .map(|equation| equation.solve())
-            .sum();
-        final_sum.to_string()
+        self.solve_format(Equation::cephalopods_format_1)
     }
 
     fn part2(&mut self) -> String {
-        let final_sum: u64 = self
-            .equations_part2
-            .iter()
-            .map(|equation| equation.solve())
-            .sum();
-        final_sum.to_string()
+        self.solve_format(Equation::cephalopods_format_2)
+    }
+
+    fn diagnostics(&self) -> Vec<String> {
+        match &self.worksheet {
+            Ok(worksheet) => worksheet
+                .warnings
+                .iter()
+                .map(|warning| format!("Worksheet warning: {}", warning))
+                .collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    fn views(&self) -> Vec<&'static str> {
+        vec!["blocks", "equations"]
+    }
+
+    /// Views are `blocks` or `equations`, optionally followed by reading options such as
+    /// `equations:right-to-left,bottom-up`
+    fn visualize(&mut self, view: &str) -> Option<Vec<String>> {
+        let (kind, options) = view.split_once(':').unwrap_or((view, ""));
+        let options: ReadingOptions = options.parse().ok()?;
+        let worksheet = match Worksheet::parse(&self.input, options) {
+            Ok(worksheet) => worksheet,
+            Err(err) => return Some(vec![format!("Invalid worksheet: {}", err)]),
+        };
+
+        match kind {
+            "blocks" => Some(Day6::show_blocks(&worksheet)),
+            "equations" => Some(Day6::show_equations(&worksheet)),
+            _ => None,
+        }
     }
 }
 
 impl SolutionFactory for Day6 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
 
-        let equations_part1 = Equation::cephalopods_format_1(&input);
-        let equations_part2 = Equation::cephalopods_format_2(&input);
+        let worksheet = Worksheet::parse(&input, ReadingOptions::default());
 
-        Box::new(Day6 {
-            equations_part1,
-            equations_part2,
-        })
+        Box::new(Day6 { input, worksheet })
     }
 }
@@ -1,42 +1,116 @@
 use core::panic;
+use std::fmt::Display;
+use std::str::FromStr;
 
-use crate::solution_traits::{read_input, Solution, SolutionFactory};
+#[cfg(feature = "bigint")]
+use crate::bigint::BigUint;
+use crate::solution_traits::{read_input, InputSource, Solution, SolutionFactory};
+
+const QUERY_USAGE: &str = "Queries: timelines [width], sources [width], where width is u64, u128 \
+or bigint (with the bigint feature) and defaults to the narrowest that fits";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    fn is_vertical(&self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+
+    /// The two directions at right angles to this one
+    fn perpendicular(&self) -> [Direction; 2] {
+        if self.is_vertical() {
+            [Direction::Left, Direction::Right]
+        } else {
+            [Direction::Up, Direction::Down]
+        }
+    }
+}
 
 #[derive(Clone, Copy)]
 enum GridItem {
-    /// Entry point for tachyon manifold beams
+    /// Entry point for tachyon manifold beams, beams leave it moving downwards
     TachyonManifold,
 
     /// Nothing here
     EmptySpace,
 
-    /// Beam moving downwards, tracks how many timelines this beam spawned from
-    TachyonBeam { timelines: u64 },
-
-    /// Splits a beam into two
+    /// Splits a beam into two, one on each side of the splitter, both keep moving the same way
     Splitter,
+
+    /// `/` mirror, turns a downward beam to the left
+    ForwardMirror,
+
+    /// `\` mirror, turns a downward beam to the right
+    BackMirror,
+
+    /// Stops any beam that hits it
+    Absorber,
+
+    /// `-`, splits a vertical beam into a left and a right beam, horizontal beams pass through
+    HorizontalSplitter,
+
+    /// `|`, splits a horizontal beam into an up and a down beam, vertical beams pass through
+    VerticalSplitter,
 }
 
 impl GridItem {
-    pub fn is_splitter(&self) -> bool {
-        matches!(self, GridItem::Splitter)
+    pub fn symbol(&self) -> char {
+        match self {
+            GridItem::TachyonManifold => 'S',
+            GridItem::EmptySpace => '.',
+            GridItem::Splitter => '^',
+            GridItem::ForwardMirror => '/',
+            GridItem::BackMirror => '\\',
+            GridItem::Absorber => '#',
+            GridItem::HorizontalSplitter => '-',
+            GridItem::VerticalSplitter => '|',
+        }
     }
 
-    pub fn is_empty_space(&self) -> bool {
-        matches!(self, GridItem::EmptySpace)
+    pub fn is_splitter(&self) -> bool {
+        matches!(
+            self,
+            GridItem::Splitter | GridItem::HorizontalSplitter | GridItem::VerticalSplitter
+        )
     }
 
-    pub fn is_beam(&self) -> bool {
-        matches!(self, GridItem::TachyonBeam { .. })
-    }
+    /// Direction a beam leaves this mirror in, or `None` if this is not a mirror
+    pub fn reflect(&self, direction: Direction) -> Option<Direction> {
+        let reflected = match (self, direction) {
+            (GridItem::ForwardMirror, Direction::Down) => Direction::Left,
+            (GridItem::ForwardMirror, Direction::Up) => Direction::Right,
+            (GridItem::ForwardMirror, Direction::Left) => Direction::Down,
+            (GridItem::ForwardMirror, Direction::Right) => Direction::Up,
+            (GridItem::BackMirror, Direction::Down) => Direction::Right,
+            (GridItem::BackMirror, Direction::Up) => Direction::Left,
+            (GridItem::BackMirror, Direction::Left) => Direction::Up,
+            (GridItem::BackMirror, Direction::Right) => Direction::Down,
+            _ => return None,
+        };
 
-    /// If this is a beam, how many timelines it has, otherwise returns 0
-    pub fn num_timelines(&self) -> u64 {
-        if let GridItem::TachyonBeam { timelines } = self {
-            *timelines
-        } else {
-            0
-        }
+        Some(reflected)
     }
 }
 
@@ -46,6 +120,11 @@ impl From<char> for GridItem {
             'S' => GridItem::TachyonManifold,
             '.' => GridItem::EmptySpace,
             '^' => GridItem::Splitter,
+            '/' => GridItem::ForwardMirror,
+            '\\' => GridItem::BackMirror,
+            '#' => GridItem::Absorber,
+            '-' => GridItem::HorizontalSplitter,
+            '|' => GridItem::VerticalSplitter,
             _ => panic!("Got undefined char for GridItem: {}", value),
         }
     }
@@ -58,10 +137,201 @@ fn create_grid_row(line: &str) -> GridRow {
     line.chars().map(|c| GridItem::from(c)).collect()
 }
 
+/// Where a beam ends up after moving
+#[derive(Clone, Copy)]
+enum BeamTarget {
+    /// Enters the cell of this beam state (see `TeleportRoom::state_index`)
+    Enter(usize),
+
+    /// Leaves the manifold through the bottom row, in this column
+    Exit(usize),
+
+    /// Leaves the manifold through the top or a side, and is no longer tracked
+    Lost,
+}
+
+/// Integer type that timelines are counted in
+pub trait TimelineCount: Clone + Ord + Display {
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+}
+
+impl TimelineCount for u64 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u64::checked_add(*self, *other)
+    }
+}
+
+impl TimelineCount for u128 {
+    fn zero() -> Self {
+        0
+    }
+
+    fn one() -> Self {
+        1
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        u128::checked_add(*self, *other)
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl TimelineCount for BigUint {
+    fn zero() -> Self {
+        BigUint::default()
+    }
+
+    fn one() -> Self {
+        BigUint::from(1)
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Some(self + other)
+    }
+}
+
+/// Which `TimelineCount` type to count in
+#[derive(Clone, Copy)]
+pub enum CountWidth {
+    U64,
+    U128,
+    #[cfg(feature = "bigint")]
+    Big,
+}
+
+impl CountWidth {
+    /// Every available width, narrowest first
+    #[cfg(not(feature = "bigint"))]
+    const ALL: [CountWidth; 2] = [CountWidth::U64, CountWidth::U128];
+    #[cfg(feature = "bigint")]
+    const ALL: [CountWidth; 3] = [CountWidth::U64, CountWidth::U128, CountWidth::Big];
+}
+
+impl FromStr for CountWidth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u64" => Ok(CountWidth::U64),
+            "u128" => Ok(CountWidth::U128),
+            #[cfg(feature = "bigint")]
+            "bigint" => Ok(CountWidth::Big),
+            _ => Err(format!("Unknown count width: {}", s)),
+        }
+    }
+}
+
+impl Display for CountWidth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CountWidth::U64 => write!(f, "u64"),
+            CountWidth::U128 => write!(f, "u128"),
+            #[cfg(feature = "bigint")]
+            CountWidth::Big => write!(f, "bigint"),
+        }
+    }
+}
+
+/// Evaluates `$run` with the type `$count` set to the `TimelineCount` type of `$width`
+macro_rules! with_count_type {
+    ($width:expr, $count:ident => $run:expr) => {
+        match $width {
+            CountWidth::U64 => {
+                type $count = u64;
+                $run
+            }
+            CountWidth::U128 => {
+                type $count = u128;
+                $run
+            }
+            #[cfg(feature = "bigint")]
+            CountWidth::Big => {
+                type $count = BigUint;
+                $run
+            }
+        }
+    };
+}
+
+/// Evaluates `$run`, which returns a `Result<_, CountOverflow>`, with the count type of each of
+/// `$widths` in turn until one does not overflow. Gives the value along with the width used, or
+/// `None` if every width overflows.
+macro_rules! with_first_fitting_count {
+    ($widths:expr, $count:ident => $run:expr) => {
+        $widths.iter().find_map(|&width| {
+            with_count_type!(width, $count => $run)
+                .ok()
+                .map(|value| (value, width))
+        })
+    };
+}
+
+/// A timeline count did not fit in its `TimelineCount` type
+pub struct CountOverflow;
+
+/// Number of timelines taking some route through the manifold
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Timelines<C> {
+    Count(C),
+
+    /// The route is on or after a beam cycle, so there is no limit
+    Unbounded,
+}
+
+impl<C: TimelineCount> Timelines<C> {
+    pub fn zero() -> Self {
+        Timelines::Count(C::zero())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Result<Self, CountOverflow> {
+        match (self, other) {
+            (Timelines::Count(a), Timelines::Count(b)) => {
+                a.checked_add(b).map(Timelines::Count).ok_or(CountOverflow)
+            }
+            _ => Ok(Timelines::Unbounded),
+        }
+    }
+
+    pub fn sum<'a>(mut timelines: impl Iterator<Item = &'a Self>) -> Result<Self, CountOverflow>
+    where
+        C: 'a,
+    {
+        timelines.try_fold(Self::zero(), |total, timelines| {
+            total.checked_add(timelines)
+        })
+    }
+}
+
+impl<C: Display> Display for Timelines<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Timelines::Count(count) => write!(f, "{}", count),
+            Timelines::Unbounded => write!(f, "unbounded"),
+        }
+    }
+}
+
+/// Exit timelines that started at the manifold entry point at (x, y)
+type SourceTimelines<C> = ((usize, usize), Timelines<C>);
+
 struct TeleportRoom {
     grid: Grid,
-
-    timelines: Option<u64>,
 }
 
 impl TeleportRoom {
@@ -72,34 +342,13 @@ impl TeleportRoom {
             .map(|line| create_grid_row(line))
             .collect();
 
-        TeleportRoom {
-            grid,
-            timelines: None,
-        }
+        TeleportRoom { grid }
     }
 
     fn get_item(&self, x: usize, y: usize) -> GridItem {
         self.grid[y][x]
     }
 
-    /// Sets the item at this location to a beam, if there is no existing beam sets the number
-    /// of timelines to the beam count, otherwise adds to the beam count
-    /// 
-    /// If the item at this location is not empty space or not a beam, does nothing
-    fn add_beam(&mut self, x: usize, y: usize, beam_count: u64) {
-        if let Some(row) = self.grid.get_mut(y) {
-            if let Some(item) = row.get_mut(x) {
-                if item.is_empty_space() {
-                    *item = GridItem::TachyonBeam {
-                        timelines: beam_count,
-                    }
-                } else if let GridItem::TachyonBeam { timelines } = item {
-                    *timelines += beam_count
-                }
-            }
-        }
-    }
-
     fn width(&self) -> usize {
         self.grid[0].len()
     }
@@ -108,49 +357,487 @@ impl TeleportRoom {
         self.grid.len()
     }
 
-    pub fn simulate(&mut self) -> usize {
-        let mut used_splinters = 0;
-        let max_row_idx = self.height() - 1;
-        let width = self.width();
+    /// A beam state is a beam entering the cell at (x, y) moving in `direction`
+    fn state_index(&self, x: usize, y: usize, direction: Direction) -> usize {
+        let direction_idx = Direction::ALL.iter().position(|d| *d == direction).unwrap();
+        (y * self.width() + x) * Direction::ALL.len() + direction_idx
+    }
+
+    fn state_from_index(&self, state: usize) -> (usize, usize, Direction) {
+        let cell = state / Direction::ALL.len();
+        let direction = Direction::ALL[state % Direction::ALL.len()];
+        (cell % self.width(), cell / self.width(), direction)
+    }
+
+    /// A beam entering (x, y) moving in `direction`, which may be off the grid
+    fn target(&self, x: i32, y: i32, direction: Direction) -> BeamTarget {
+        let in_columns = x >= 0 && (x as usize) < self.width();
+        if y >= self.height() as i32 && in_columns {
+            BeamTarget::Exit(x as usize)
+        } else if y < 0 || !in_columns {
+            BeamTarget::Lost
+        } else {
+            BeamTarget::Enter(self.state_index(x as usize, y as usize, direction))
+        }
+    }
+
+    /// Where the beam of `state` goes after it enters its cell, and whether the cell split it
+    fn next_beams(&self, state: usize) -> (Vec<BeamTarget>, bool) {
+        let (x, y, direction) = self.state_from_index(state);
+        let (x, y) = (x as i32, y as i32);
+        let forward = |direction: Direction| {
+            let (dx, dy) = direction.offset();
+            self.target(x + dx, y + dy, direction)
+        };
+
+        let item = self.get_item(x as usize, y as usize);
+        match item {
+            GridItem::EmptySpace | GridItem::TachyonManifold => (vec![forward(direction)], false),
+            GridItem::Splitter => {
+                let sides = direction
+                    .perpendicular()
+                    .iter()
+                    .map(|side| {
+                        let (dx, dy) = side.offset();
+                        self.target(x + dx, y + dy, direction)
+                    })
+                    .collect();
+                (sides, true)
+            }
+            GridItem::ForwardMirror | GridItem::BackMirror => {
+                (vec![forward(item.reflect(direction).unwrap())], false)
+            }
+            GridItem::Absorber => (vec![], false),
+            GridItem::HorizontalSplitter | GridItem::VerticalSplitter => {
+                let splits =
+                    direction.is_vertical() == matches!(item, GridItem::HorizontalSplitter);
+                if splits {
+                    let beams = direction.perpendicular().into_iter().map(forward).collect();
+                    (beams, true)
+                } else {
+                    (vec![forward(direction)], false)
+                }
+            }
+        }
+    }
+
+    /// Each manifold entry point, with where its beam goes first
+    fn sources(&self) -> Vec<((usize, usize), BeamTarget)> {
+        let mut sources = vec![];
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                if matches!(self.get_item(x, y), GridItem::TachyonManifold) {
+                    let target = self.target(x as i32, y as i32 + 1, Direction::Down);
+                    sources.push(((x, y), target));
+                }
+            }
+        }
+
+        sources
+    }
+
+    /// Finds every beam state reachable from the given sources, without counting timelines
+    fn reach(&self, sources: &[BeamTarget]) -> BeamGraph {
+        let num_states = self.width() * self.height() * Direction::ALL.len();
+        let mut reached = vec![false; num_states];
+        let mut in_degree = vec![0; num_states];
+        let mut splitter_used = vec![false; self.width() * self.height()];
+        let mut to_visit: Vec<usize> = sources
+            .iter()
+            .filter_map(|source| match source {
+                BeamTarget::Enter(state) => Some(*state),
+                _ => None,
+            })
+            .collect();
+        to_visit.iter().for_each(|&state| reached[state] = true);
+
+        while let Some(state) = to_visit.pop() {
+            let (next, split) = self.next_beams(state);
+            if split {
+                splitter_used[state / Direction::ALL.len()] = true;
+            }
 
-        for y in 0..max_row_idx {
-            for x in 0..width {
-                let item = self.get_item(x, y);
-                match item {
-                    GridItem::EmptySpace => { /* Do nothing */ }
-                    GridItem::TachyonManifold => {
-                        // Spawn beam
-                        self.add_beam(x, y + 1, 1);
+            for target in next {
+                if let BeamTarget::Enter(next_state) = target {
+                    in_degree[next_state] += 1;
+                    if !reached[next_state] {
+                        reached[next_state] = true;
+                        to_visit.push(next_state);
                     }
-                    GridItem::TachyonBeam { timelines } => {
-                        // move beam downward
-                        let item_below_beam = self.get_item(x, y + 1);
-                        if item_below_beam.is_splitter() {
-                            self.add_beam(x - 1, y + 1, timelines);
-                            self.add_beam(x + 1, y + 1, timelines);
-                        } else {
-                            self.add_beam(x, y + 1, timelines);
+                }
+            }
+        }
+
+        BeamGraph {
+            reached,
+            in_degree,
+            splitter_used,
+        }
+    }
+
+    /// Number of splitters that split at least one beam. Timelines are not counted, so this
+    /// never overflows.
+    pub fn splits(&self) -> usize {
+        let sources: Vec<BeamTarget> = self
+            .sources()
+            .into_iter()
+            .map(|(_, target)| target)
+            .collect();
+        let graph = self.reach(&sources);
+        graph.splitter_used.iter().filter(|&&used| used).count()
+    }
+
+    /// Follows every beam from the given sources, recording the timelines through each beam
+    /// state and leaving through each column of the bottom row.
+    ///
+    /// Beam states form a graph, which only has cycles when mirrors or splitters send a beam
+    /// back over its own path. Timelines are counted over the graph in topological order, so
+    /// states on or after a cycle have unbounded timelines.
+    fn trace<C: TimelineCount>(
+        &self,
+        sources: &[BeamTarget],
+    ) -> Result<BeamTrace<C>, CountOverflow> {
+        let BeamGraph {
+            reached,
+            mut in_degree,
+            ..
+        } = self.reach(sources);
+        let num_states = reached.len();
+
+        // count timelines, a state is only processed once every beam leading into it has been
+        let one = Timelines::Count(C::one());
+        let mut timelines = vec![Timelines::zero(); num_states];
+        let mut exits = vec![Timelines::zero(); self.width()];
+        for source in sources.iter() {
+            match source {
+                BeamTarget::Enter(state) => {
+                    timelines[*state] = timelines[*state].checked_add(&one)?
+                }
+                BeamTarget::Exit(x) => exits[*x] = exits[*x].checked_add(&one)?,
+                BeamTarget::Lost => {}
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..num_states)
+            .filter(|&state| reached[state] && in_degree[state] == 0)
+            .collect();
+        let mut processed = vec![false; num_states];
+        while let Some(state) = ready.pop() {
+            processed[state] = true;
+            for target in self.next_beams(state).0 {
+                match target {
+                    BeamTarget::Enter(next_state) => {
+                        timelines[next_state] =
+                            timelines[next_state].checked_add(&timelines[state])?;
+                        in_degree[next_state] -= 1;
+                        if in_degree[next_state] == 0 {
+                            ready.push(next_state);
                         }
                     }
+                    BeamTarget::Exit(x) => exits[x] = exits[x].checked_add(&timelines[state])?,
+                    BeamTarget::Lost => {}
+                }
+            }
+        }
 
-                    GridItem::Splitter => {
-                        if self.get_item(x, y - 1).is_beam() {
-                            used_splinters += 1
-                        }
+        // unprocessed states are on or after a cycle, and everything after them is unprocessed
+        for state in (0..num_states).filter(|&state| reached[state] && !processed[state]) {
+            timelines[state] = Timelines::Unbounded;
+            for target in self.next_beams(state).0 {
+                if let BeamTarget::Exit(x) = target {
+                    exits[x] = Timelines::Unbounded;
+                }
+            }
+        }
+
+        Ok(BeamTrace {
+            reached,
+            timelines,
+            exits,
+        })
+    }
+
+    /// Follows every beam from every manifold entry point. The grid is left untouched, so this
+    /// can be run any number of times.
+    pub fn simulate<C: TimelineCount>(&self) -> Result<Simulation<'_, C>, CountOverflow> {
+        let sources = self.sources();
+        let targets: Vec<BeamTarget> = sources.iter().map(|(_, target)| *target).collect();
+        let trace = self.trace::<C>(&targets)?;
+        let timelines = Timelines::sum(trace.exits.iter())?;
+
+        Ok(Simulation {
+            room: self,
+            sources,
+            trace,
+            timelines,
+        })
+    }
+}
+
+/// Beam states reachable from a set of sources
+struct BeamGraph {
+    reached: Vec<bool>,
+
+    /// Number of beams leading into each state
+    in_degree: Vec<usize>,
+
+    /// Whether the item in each cell split at least one beam
+    splitter_used: Vec<bool>,
+}
+
+/// Outcome of following every beam from every manifold entry point
+pub struct Simulation<'a, C> {
+    room: &'a TeleportRoom,
+
+    /// Each manifold entry point, with where its beam goes first
+    sources: Vec<((usize, usize), BeamTarget)>,
+
+    trace: BeamTrace<C>,
+
+    /// Timelines leaving through the bottom row
+    timelines: Timelines<C>,
+}
+
+impl<C: TimelineCount> Simulation<'_, C> {
+    pub fn timelines(&self) -> &Timelines<C> {
+        &self.timelines
+    }
+
+    /// Timelines leaving through the bottom row that started at each manifold entry point, by
+    /// (x, y) position of the entry point.
+    ///
+    /// The paths to the exits are counted backwards from the bottom row over the traced states,
+    /// in reverse topological order. States that reach a cycle which reaches the bottom row
+    /// have unbounded timelines, the same as when tracing forwards from each entry point.
+    pub fn source_timelines(&self) -> Result<Vec<SourceTimelines<C>>, CountOverflow> {
+        let room = self.room;
+        let reached = &self.trace.reached;
+        let num_states = reached.len();
+        let one = Timelines::Count(C::one());
+
+        let mut predecessors = vec![vec![]; num_states];
+        let mut to_visit = vec![];
+        for state in (0..num_states).filter(|&state| reached[state]) {
+            for target in room.next_beams(state).0 {
+                match target {
+                    BeamTarget::Enter(next_state) => predecessors[next_state].push(state),
+                    BeamTarget::Exit(_) => to_visit.push(state),
+                    BeamTarget::Lost => {}
+                }
+            }
+        }
+
+        // only states that still lead to the bottom row can add paths
+        let mut can_exit = vec![false; num_states];
+        to_visit.iter().for_each(|&state| can_exit[state] = true);
+        while let Some(state) = to_visit.pop() {
+            for &previous in predecessors[state].iter() {
+                if !can_exit[previous] {
+                    can_exit[previous] = true;
+                    to_visit.push(previous);
+                }
+            }
+        }
+
+        // a state is counted once every state it leads to that can exit has been
+        let mut out_degree = vec![0; num_states];
+        for state in (0..num_states).filter(|&state| can_exit[state]) {
+            for &previous in predecessors[state].iter() {
+                out_degree[previous] += 1;
+            }
+        }
+
+        let mut paths = vec![Timelines::zero(); num_states];
+        let mut ready: Vec<usize> = (0..num_states)
+            .filter(|&state| can_exit[state] && out_degree[state] == 0)
+            .collect();
+        let mut processed = vec![false; num_states];
+        while let Some(state) = ready.pop() {
+            processed[state] = true;
+            for target in room.next_beams(state).0 {
+                paths[state] = match target {
+                    BeamTarget::Enter(next_state) if can_exit[next_state] => {
+                        paths[state].checked_add(&paths[next_state])?
                     }
+                    BeamTarget::Exit(_) => paths[state].checked_add(&one)?,
+                    _ => continue,
+                };
+            }
+
+            for &previous in predecessors[state].iter() {
+                out_degree[previous] -= 1;
+                if out_degree[previous] == 0 {
+                    ready.push(previous);
                 }
             }
         }
 
-        let timelines: u64 = self
-            .grid
-            .last()
-            .unwrap()
+        // unprocessed states reach a cycle, and everything on it can exit
+        for state in (0..num_states).filter(|&state| can_exit[state] && !processed[state]) {
+            paths[state] = Timelines::Unbounded;
+        }
+
+        Ok(self
+            .sources
             .iter()
-            .map(|item| item.num_timelines())
-            .sum();
-        self.timelines = Some(timelines);
-        used_splinters
+            .map(|&(position, target)| {
+                let timelines = match target {
+                    BeamTarget::Enter(state) => paths[state].clone(),
+                    BeamTarget::Exit(_) => one.clone(),
+                    BeamTarget::Lost => Timelines::zero(),
+                };
+                (position, timelines)
+            })
+            .collect())
+    }
+
+    /// Timelines through every cell and out of every exit column
+    pub fn report(&self) -> Result<SimulationReport<C>, CountOverflow> {
+        let room = self.room;
+        let mut cells = vec![];
+        let mut column_traffic = vec![Timelines::zero(); room.width()];
+        for (y, row) in room.grid.iter().enumerate() {
+            let mut cell_row = vec![];
+            for (x, &item) in row.iter().enumerate() {
+                let entering = Direction::ALL
+                    .iter()
+                    .map(|&direction| &self.trace.timelines[room.state_index(x, y, direction)]);
+                let timelines = Timelines::sum(entering)?;
+                column_traffic[x] = column_traffic[x].checked_add(&timelines)?;
+                cell_row.push(CellTraffic { item, timelines });
+            }
+            cells.push(cell_row);
+        }
+
+        Ok(SimulationReport {
+            cells,
+            column_traffic,
+            exits: self.trace.exits.clone(),
+        })
+    }
+}
+
+/// Every beam state reached from the manifold entry points
+struct BeamTrace<C> {
+    reached: Vec<bool>,
+
+    /// Timelines entering each beam state, zero for states no beam reaches
+    timelines: Vec<Timelines<C>>,
+
+    /// Timelines leaving through the bottom row, by column
+    exits: Vec<Timelines<C>>,
+}
+
+struct CellTraffic<C> {
+    item: GridItem,
+
+    /// Timelines entering this cell, from any direction
+    timelines: Timelines<C>,
+}
+
+/// Where the timelines of a simulation went, cell by cell
+pub struct SimulationReport<C> {
+    cells: Vec<Vec<CellTraffic<C>>>,
+
+    /// Timelines through all the cells of each column
+    column_traffic: Vec<Timelines<C>>,
+
+    /// Timelines leaving through the bottom row, by column
+    exits: Vec<Timelines<C>>,
+}
+
+impl<C: TimelineCount> SimulationReport<C> {
+    /// Timelines through each splitter, by (x, y) position
+    pub fn splitter_traffic(&self) -> Vec<((usize, usize), &Timelines<C>)> {
+        let mut splitters = vec![];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.item.is_splitter() {
+                    splitters.push(((x, y), &cell.timelines));
+                }
+            }
+        }
+
+        splitters
+    }
+
+    /// Positions of splitters no beam ever reaches
+    pub fn unused_splitters(&self) -> Vec<(usize, usize)> {
+        self.splitter_traffic()
+            .into_iter()
+            .filter(|(_, timelines)| timelines.is_zero())
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Leftmost column with the most timelines through it
+    pub fn busiest_column(&self) -> Option<(usize, &Timelines<C>)> {
+        self.column_traffic
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|(_, timelines)| *timelines)
+    }
+
+    /// Draws the grid with each cell a beam passes through replaced by the number of digits in
+    /// its timeline count, or `*` if unbounded. A row below the grid shows the exits the same way.
+    pub fn heat_map(&self) -> String {
+        let heat = |timelines: &Timelines<C>| match timelines {
+            Timelines::Count(count) => match count.to_string().len() {
+                digits @ 1..=9 => char::from_digit(digits as u32, 10).unwrap(),
+                _ => '+',
+            },
+            Timelines::Unbounded => '*',
+        };
+
+        let mut lines: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        if cell.timelines.is_zero() {
+                            cell.item.symbol()
+                        } else {
+                            heat(&cell.timelines)
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        lines.push("=".repeat(self.exits.len()));
+        lines.push(
+            self.exits
+                .iter()
+                .map(|exit| if exit.is_zero() { ' ' } else { heat(exit) })
+                .collect(),
+        );
+
+        lines.join("\n")
+    }
+
+    /// One `x,y,item,timelines` row per cell, followed by one row per exit column with `y` one
+    /// past the bottom row and `exit` as the item
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec![String::from("x,y,item,timelines")];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                lines.push(format!(
+                    "{},{},{},{}",
+                    x,
+                    y,
+                    cell.item.symbol(),
+                    cell.timelines
+                ));
+            }
+        }
+
+        for (x, exit) in self.exits.iter().enumerate() {
+            lines.push(format!("{},{},exit,{}", x, self.cells.len(), exit));
+        }
+
+        lines.join("\n")
     }
 }
 
@@ -158,25 +845,139 @@ pub struct Day7 {
     teleport_room: TeleportRoom,
 }
 
+impl Day7 {
+    /// One line per manifold entry point, with the exit timelines that started there
+    fn describe_sources<C: TimelineCount>(
+        simulation: &Simulation<C>,
+    ) -> Result<Vec<String>, CountOverflow> {
+        let sources = simulation.source_timelines()?;
+        Ok(sources
+            .iter()
+            .map(|((x, y), timelines)| format!("Timelines from S at ({}, {}): {}", x, y, timelines))
+            .collect())
+    }
+
+    fn describe_report<C: TimelineCount>(
+        simulation: &Simulation<C>,
+    ) -> Result<Vec<String>, CountOverflow> {
+        let report = simulation.report()?;
+        let splitters = report.splitter_traffic().len();
+        let mut lines = vec![format!(
+            "Splitters hit: {} of {}",
+            splitters - report.unused_splitters().len(),
+            splitters
+        )];
+        if let Some((column, timelines)) = report.busiest_column() {
+            lines.push(format!(
+                "Busiest column: {} ({} timelines)",
+                column, timelines
+            ));
+        }
+
+        Ok(lines)
+    }
+
+    /// Source lines and report lines, both from one simulation
+    fn describe<C: TimelineCount>(&self) -> Result<Vec<String>, CountOverflow> {
+        let simulation = self.teleport_room.simulate::<C>()?;
+        let mut lines = Self::describe_sources(&simulation)?;
+        lines.extend(Self::describe_report(&simulation)?);
+        Ok(lines)
+    }
+
+    fn render<C: TimelineCount>(&self, kind: &str) -> Result<Option<String>, CountOverflow> {
+        let report = self.teleport_room.simulate::<C>()?.report()?;
+        Ok(match kind {
+            "heatmap" => Some(report.heat_map()),
+            "csv" => Some(report.to_csv()),
+            _ => None,
+        })
+    }
+}
+
 impl Solution for Day7 {
     fn part1(&mut self) -> String {
-        let beam_splits = self.teleport_room.simulate();
-        beam_splits.to_string()
+        self.teleport_room.splits().to_string()
     }
 
     fn part2(&mut self) -> String {
-        let timelines = self
+        let timelines = with_first_fitting_count!(CountWidth::ALL, C => self
             .teleport_room
-            .timelines
-            .expect("No timeline set (simulation was not run)");
-        timelines.to_string()
+            .simulate::<C>()
+            .map(|simulation| simulation.timelines().to_string()));
+        match timelines {
+            Some((timelines, _)) => timelines,
+            None => String::from("overflow (enable bigint)"),
+        }
+    }
+
+    fn diagnostics(&self) -> Vec<String> {
+        match with_first_fitting_count!(CountWidth::ALL, C => self.describe::<C>()) {
+            Some((lines, width)) => {
+                let mut diagnostics = vec![format!("Timelines counted as {}", width)];
+                diagnostics.extend(lines);
+                diagnostics
+            }
+            None => vec![],
+        }
+    }
+
+    /// Answers one query line, see `QUERY_USAGE` for the commands
+    fn query(&mut self, command: &str) -> Result<String, String> {
+        let (name, arg) = command
+            .trim()
+            .split_once(' ')
+            .map(|(name, arg)| (name, arg.trim()))
+            .unwrap_or((command.trim(), ""));
+        let widths = match arg {
+            "" => CountWidth::ALL.to_vec(),
+            width => vec![width.parse()?],
+        };
+
+        let lines = match name {
+            "timelines" => with_first_fitting_count!(widths, C => self
+                .teleport_room
+                .simulate::<C>()
+                .map(|simulation| vec![simulation.timelines().to_string()])),
+            "sources" => with_first_fitting_count!(widths, C => self
+                .teleport_room
+                .simulate::<C>()
+                .and_then(|simulation| Self::describe_sources(&simulation))),
+            _ => {
+                return Err(format!(
+                    "Unknown query: {}\n{}",
+                    command.trim(),
+                    QUERY_USAGE
+                ))
+            }
+        };
+
+        match lines {
+            Some((lines, _)) => Ok(lines.join("\n")),
+            None => Err(format!("Timelines overflow {}", widths[widths.len() - 1])),
+        }
+    }
+
+    fn views(&self) -> Vec<&'static str> {
+        vec!["heatmap", "csv"]
+    }
+
+    /// Views are `heatmap` or `csv`, optionally followed by a count width such as `csv:u128`
+    fn visualize(&mut self, view: &str) -> Option<Vec<String>> {
+        let (kind, widths) = match view.split_once(':') {
+            Some((kind, width)) => (kind, vec![width.parse().ok()?]),
+            None => (view, CountWidth::ALL.to_vec()),
+        };
+
+        let (frame, _) = with_first_fitting_count!(widths, C => self.render::<C>(kind))?;
+        frame.map(|frame| vec![frame])
     }
 }
 
 impl SolutionFactory for Day7 {
-    fn init(inputs_dir: &str, day: u8) -> Box<dyn Solution> {
+    fn init(input: &InputSource, day: u8) -> Box<dyn Solution> {
         let _debug_file = Some(String::from("sample.txt"));
-        let input_bytes = read_input(inputs_dir, day, None);
+        let input_bytes = read_input(input, day, None);
         let input = String::from_utf8_lossy(&input_bytes).to_string();
 
         let teleport_room = TeleportRoom::from_file(&input);
@@ -1,23 +1,68 @@
-use crate::solution_traits::{Solution, SolutionFactory};
+use std::time::Duration;
 
-mod day01;
+use crate::solution_traits::{InputSource, Solution, SolutionFactory, StreamSolver};
+
+pub mod day01;
 mod day02;
 mod day03;
-mod day04;
+pub mod day04;
 mod day05;
 mod day06;
 mod day07;
 
+/// Loads a day's solution from its input
+type Factory = fn(&InputSource, u8) -> Box<dyn Solution>;
+
+/// A day that has a solution
+struct DayEntry {
+    init: Factory,
+
+    /// Time its init and each of its parts must stay within
+    budget: Duration,
+}
+
+/// The entry of a given day, the one list of days that have a solution
+fn get_entry_for_day(day: u8) -> Option<DayEntry> {
+    let (init, budget_ms): (Factory, u64) = match day {
+        1 => (day01::Day1::init, 50),
+        2 => (day02::Day2::init, 100),
+        3 => (day03::Day3::init, 100),
+        4 => (day04::Day4::init, 100),
+        5 => (day05::Day5::init, 50),
+        6 => (day06::Day6::init, 50),
+        7 => (day07::Day7::init, 100),
+        _ => return None,
+    };
+
+    Some(DayEntry {
+        init,
+        budget: Duration::from_millis(budget_ms),
+    })
+}
+
 /// Factory function to get solution for a given day
-pub fn get_solution_for_day(inputs_dir: &str, day: u8) -> Option<Box<dyn Solution>> {
+pub fn get_solution_for_day(input: &InputSource, day: u8) -> Option<Box<dyn Solution>> {
+    get_entry_for_day(day).map(|entry| (entry.init)(input, day))
+}
+
+/// Whether `get_solution_for_day` has a solution for a given day, without loading its input
+pub fn has_solution_for_day(day: u8) -> bool {
+    get_entry_for_day(day).is_some()
+}
+
+/// Time budget of a given day, which its init and each of its parts must stay within
+pub fn get_budget_for_day(day: u8) -> Option<Duration> {
+    get_entry_for_day(day).map(|entry| entry.budget)
+}
+
+/// Time budget of all days together
+pub const TOTAL_BUDGET: Duration = Duration::from_secs(1);
+
+/// Factory function to get the streaming solver for a given day, if it has one
+pub fn get_stream_solver_for_day(day: u8) -> Option<StreamSolver> {
     match day {
-        1 => Some(day01::Day1::init(inputs_dir, day)),
-        2 => Some(day02::Day2::init(inputs_dir, day)),
-        3 => Some(day03::Day3::init(inputs_dir, day)),
-        4 => Some(day04::Day4::init(inputs_dir, day)),
-        5 => Some(day05::Day5::init(inputs_dir, day)),
-        6 => Some(day06::Day6::init(inputs_dir, day)),
-        7 => Some(day07::Day7::init(inputs_dir, day)),
+        1 => Some(day01::stream_rotations),
+        5 => Some(day05::stream_inventory),
         _ => None,
     }
 }
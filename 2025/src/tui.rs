@@ -0,0 +1,367 @@
+use std::io::Write;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::bench::format_duration;
+use crate::isolation::panic_message;
+use crate::runner::{SolutionRunner, SolvedDay};
+use crate::solution_traits::Solution;
+
+const HELP: &str = "j/k select  enter run  a run all  v next view  h/l frames  q quit";
+
+/// Puts the terminal in raw mode on the alternate screen, restoring it when dropped
+struct RawTerminal {
+    original: libc::termios,
+}
+
+impl RawTerminal {
+    fn enter() -> RawTerminal {
+        if unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+            panic!("The dashboard needs a terminal on stdin");
+        }
+
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            panic!(
+                "Failed to read terminal settings: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        let mut raw = original;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) } != 0 {
+            panic!(
+                "Failed to enter raw mode: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        // alternate screen, hidden cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        RawTerminal { original }
+    }
+
+    /// Rows and columns of the terminal
+    fn size(&self) -> (usize, usize) {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } != 0
+            || size.ws_row == 0
+        {
+            return (24, 80);
+        }
+        (size.ws_row as usize, size.ws_col as usize)
+    }
+
+    /// Waits for input and returns the keys in it, as a paste or fast typing can bring several
+    fn read_keys(&self) -> Vec<Key> {
+        let mut buffer = [0u8; 64];
+        let count = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), 64) };
+        if count <= 0 {
+            return vec![Key::Quit];
+        }
+
+        let mut input = &buffer[..count as usize];
+        let mut keys = vec![];
+        while !input.is_empty() {
+            // arrow keys are an escape sequence, everything else is one byte
+            let len = if input.starts_with(b"\x1b[") && input.len() >= 3 {
+                3
+            } else {
+                1
+            };
+            keys.push(match &input[..len] {
+                b"q" | b"\x03" | b"\x04" => Key::Quit,
+                b"k" | b"\x1b[A" => Key::Up,
+                b"j" | b"\x1b[B" => Key::Down,
+                b"h" | b"\x1b[D" => Key::Left,
+                b"l" | b"\x1b[C" => Key::Right,
+                b"\r" | b"\n" | b"r" => Key::Run,
+                b"a" => Key::RunAll,
+                b"v" => Key::NextView,
+                _ => Key::Other,
+            });
+            input = &input[len..];
+        }
+
+        keys
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = std::io::stdout().flush();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original) };
+    }
+}
+
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Run,
+    RunAll,
+    NextView,
+    Quit,
+    Other,
+}
+
+/// Result of the last run of a day
+enum DayStatus {
+    NotRun,
+    Solved(SolvedDay),
+
+    /// Panic message
+    Failed(String),
+}
+
+impl DayStatus {
+    fn label(&self) -> &'static str {
+        match self {
+            DayStatus::NotRun => "not run",
+            DayStatus::Solved(_) => "ok",
+            DayStatus::Failed(_) => "failed",
+        }
+    }
+}
+
+/// The selected day's loaded solution and the view being shown
+struct Viewer {
+    solution: Box<dyn Solution>,
+    views: Vec<&'static str>,
+    view: usize,
+    frames: Vec<String>,
+    frame: usize,
+}
+
+struct Dashboard<'a> {
+    runner: &'a SolutionRunner,
+    days: Vec<(u8, DayStatus)>,
+    selected: usize,
+    viewer: Option<Viewer>,
+
+    /// Shown at the bottom until the next key
+    message: Option<String>,
+}
+
+impl Dashboard<'_> {
+    fn day(&self) -> u8 {
+        self.days[self.selected].0
+    }
+
+    fn run(&mut self, index: usize) {
+        let day = self.days[index].0;
+        self.days[index].1 = match catch_unwind(AssertUnwindSafe(|| self.runner.solve(day))) {
+            Ok(Some(solved)) => DayStatus::Solved(solved),
+            Ok(None) => DayStatus::Failed(String::from("no solution")),
+            Err(payload) => DayStatus::Failed(panic_message(&*payload)),
+        };
+    }
+
+    /// Shows the selected day's next view, loading the day first if needed
+    fn next_view(&mut self) {
+        let day = self.day();
+        let mut viewer = match self.viewer.take() {
+            Some(mut viewer) => {
+                viewer.view = (viewer.view + 1) % viewer.views.len();
+                viewer
+            }
+            None => match catch_unwind(AssertUnwindSafe(|| self.runner.solution(day))) {
+                Ok(Some(solution)) => Viewer {
+                    views: solution.views(),
+                    solution,
+                    view: 0,
+                    frames: vec![],
+                    frame: 0,
+                },
+                Ok(None) => return,
+                Err(payload) => {
+                    self.message = Some(panic_message(&*payload));
+                    return;
+                }
+            },
+        };
+
+        if viewer.views.is_empty() {
+            self.message = Some(format!("Day {} has no views", day));
+            return;
+        }
+
+        let view = viewer.views[viewer.view];
+        match catch_unwind(AssertUnwindSafe(|| viewer.solution.visualize(view))) {
+            Ok(frames) => viewer.frames = frames.unwrap_or_default(),
+            Err(payload) => {
+                self.message = Some(panic_message(&*payload));
+                viewer.frames = vec![];
+            }
+        }
+        viewer.frame = 0;
+        self.viewer = Some(viewer);
+    }
+
+    /// Acts on a key, returning false once the dashboard should close
+    fn press(&mut self, key: Key) -> bool {
+        match key {
+            Key::Quit => return false,
+            Key::Up => self.select(self.selected.saturating_sub(1)),
+            Key::Down => self.select((self.selected + 1).min(self.days.len() - 1)),
+            Key::Run => {
+                self.run(self.selected);
+                self.message = Some(format!("Ran day {}", self.day()));
+            }
+            Key::RunAll => {
+                (0..self.days.len()).for_each(|index| self.run(index));
+                self.message = Some(String::from("Ran every day"));
+            }
+            Key::NextView => self.next_view(),
+            Key::Left => {
+                if let Some(viewer) = &mut self.viewer {
+                    viewer.frame = viewer.frame.saturating_sub(1);
+                }
+            }
+            Key::Right => {
+                if let Some(viewer) = &mut self.viewer {
+                    viewer.frame = (viewer.frame + 1).min(viewer.frames.len().saturating_sub(1));
+                }
+            }
+            Key::Other => {}
+        }
+
+        true
+    }
+
+    fn select(&mut self, selected: usize) {
+        if selected != self.selected {
+            self.selected = selected;
+            self.viewer = None;
+        }
+    }
+
+    /// Lines of the whole screen, fitted to `rows` by `columns`
+    fn render(&self, rows: usize, columns: usize) -> Vec<String> {
+        let mut lines = vec![format!("Advent of Code 2025  |  {}", HELP), String::new()];
+
+        for (index, (day, status)) in self.days.iter().enumerate() {
+            let marker = if index == self.selected { ">" } else { " " };
+            let time = match status {
+                DayStatus::Solved(solved) => {
+                    format_duration(solved.init + solved.part1 + solved.part2)
+                }
+                _ => String::new(),
+            };
+            lines.push(format!(
+                "{} Day {:>2}  {:<7}  {:>11}",
+                marker,
+                day,
+                status.label(),
+                time
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push(format!("Day {}", self.day()));
+        match &self.days[self.selected].1 {
+            DayStatus::NotRun => lines.push(String::from("  Press enter to run")),
+            DayStatus::Failed(message) => {
+                lines.extend(message.lines().map(|line| format!("  Error: {}", line)))
+            }
+            DayStatus::Solved(solved) => {
+                lines.push(format!(
+                    "  Part 1: {:<20} {}",
+                    solved.answers[0],
+                    format_duration(solved.part1)
+                ));
+                lines.push(format!(
+                    "  Part 2: {:<20} {}",
+                    solved.answers[1],
+                    format_duration(solved.part2)
+                ));
+                lines.push(format!(
+                    "  Init:   {:<20} {}",
+                    "",
+                    format_duration(solved.init)
+                ));
+                lines.extend(solved.diagnostics.iter().map(|line| format!("  {}", line)));
+            }
+        }
+
+        if let Some(viewer) = &self.viewer {
+            lines.push(String::new());
+            lines.push(format!(
+                "View {} ({} of {}), frame {} of {}",
+                viewer.views[viewer.view],
+                viewer.view + 1,
+                viewer.views.len(),
+                (viewer.frame + 1).min(viewer.frames.len()),
+                viewer.frames.len()
+            ));
+            if let Some(frame) = viewer.frames.get(viewer.frame) {
+                lines.extend(frame.lines().map(String::from));
+            }
+        }
+
+        lines.truncate(rows - 1);
+        while lines.len() < rows - 1 {
+            lines.push(String::new());
+        }
+        lines.push(self.message.clone().unwrap_or_default());
+
+        lines
+            .into_iter()
+            .map(|line| line.chars().take(columns).collect())
+            .collect()
+    }
+}
+
+/// Interactive dashboard listing the runner's days, running them on a keypress and showing
+/// their answers, timings, errors and views
+pub fn dashboard(runner: &SolutionRunner) {
+    let days: Vec<(u8, DayStatus)> = runner
+        .days()
+        .into_iter()
+        .map(|day| (day, DayStatus::NotRun))
+        .collect();
+    if days.is_empty() {
+        println!("No days to show");
+        return;
+    }
+
+    let mut dashboard = Dashboard {
+        runner,
+        days,
+        selected: 0,
+        viewer: None,
+        message: None,
+    };
+
+    // panics of the days are shown on the dashboard instead of printed over it
+    let terminal = RawTerminal::enter();
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let result = catch_unwind(AssertUnwindSafe(|| loop {
+        let (rows, columns) = terminal.size();
+        print!(
+            "\x1b[H\x1b[2J{}",
+            dashboard.render(rows, columns).join("\r\n")
+        );
+        std::io::stdout().flush().expect("Failed to flush stdout");
+
+        dashboard.message = None;
+        if !terminal
+            .read_keys()
+            .into_iter()
+            .all(|key| dashboard.press(key))
+        {
+            break;
+        }
+    }));
+
+    drop(terminal);
+    std::panic::set_hook(previous_hook);
+    if let Err(payload) = result {
+        eprintln!("Dashboard failed: {}", panic_message(&*payload));
+        std::panic::resume_unwind(payload);
+    }
+}
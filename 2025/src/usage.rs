@@ -0,0 +1,87 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+/// Snapshot of this process's resource usage, from `getrusage`
+pub struct Usage {
+    wall: Instant,
+    user: Duration,
+    system: Duration,
+
+    /// High-water mark of the resident set since the process started
+    max_rss_kib: u64,
+
+    minor_faults: u64,
+    major_faults: u64,
+}
+
+impl Usage {
+    pub fn now() -> Usage {
+        let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+        if unsafe { libc::getrusage(libc::RUSAGE_SELF, &mut usage) } != 0 {
+            panic!(
+                "Failed to get resource usage: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        let to_duration = |time: libc::timeval| {
+            Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+        };
+
+        Usage {
+            wall: Instant::now(),
+            user: to_duration(usage.ru_utime),
+            system: to_duration(usage.ru_stime),
+            max_rss_kib: usage.ru_maxrss as u64,
+            minor_faults: usage.ru_minflt as u64,
+            major_faults: usage.ru_majflt as u64,
+        }
+    }
+
+    /// Resources used between `start` and this snapshot
+    pub fn since(&self, start: &Usage) -> PhaseUsage {
+        PhaseUsage {
+            wall: self.wall - start.wall,
+            user: self.user.saturating_sub(start.user),
+            system: self.system.saturating_sub(start.system),
+            max_rss_kib: self.max_rss_kib,
+            rss_growth_kib: self.max_rss_kib - start.max_rss_kib,
+            minor_faults: self.minor_faults - start.minor_faults,
+            major_faults: self.major_faults - start.major_faults,
+        }
+    }
+}
+
+/// Resources used by one phase of a run, such as a day's init or one of its parts
+pub struct PhaseUsage {
+    pub wall: Duration,
+    pub user: Duration,
+    pub system: Duration,
+
+    /// The process-wide high-water mark at the end of the phase, the kernel does not track
+    /// the peak of a single phase
+    pub max_rss_kib: u64,
+
+    /// How much the high-water mark rose during the phase
+    pub rss_growth_kib: u64,
+
+    pub minor_faults: u64,
+    pub major_faults: u64,
+}
+
+impl Display for PhaseUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        write!(
+            f,
+            "wall {:.3}ms, user {:.3}ms, sys {:.3}ms, max rss {} KiB (+{}), page faults {} minor / {} major",
+            ms(self.wall),
+            ms(self.user),
+            ms(self.system),
+            self.max_rss_kib,
+            self.rss_growth_kib,
+            self.minor_faults,
+            self.major_faults
+        )
+    }
+}
@@ -0,0 +1,261 @@
+use std::ffi::CString;
+use std::os::unix::process::CommandExt;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::process::Command;
+
+use crate::runner::SolutionRunner;
+
+/// How long to wait for more events after one arrives, so that one save runs the day once
+const SETTLE_MS: i32 = 100;
+
+/// Answers of the previous run, handed to the rebuilt binary so it can diff against them
+const PREVIOUS_ANSWERS_VAR: &str = "ADVENT_WATCH_PREVIOUS";
+
+/// A file in a watched directory changed
+struct FileEvent {
+    /// Watch descriptor of the directory, as returned by `Inotify::watch_dir`
+    wd: i32,
+
+    name: String,
+}
+
+/// Linux inotify instance, closed when dropped
+struct Inotify {
+    fd: i32,
+}
+
+impl Inotify {
+    pub fn new() -> Inotify {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            panic!(
+                "Failed to start inotify: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        Inotify { fd }
+    }
+
+    /// Watches a directory for files being written, created, moved in or deleted
+    pub fn watch_dir(&self, dir: &str) -> i32 {
+        let path = CString::new(dir).expect("Directory path contains a NUL byte");
+        let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE | libc::IN_DELETE;
+        let wd = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), mask) };
+        if wd < 0 {
+            panic!(
+                "Failed to watch {}: {}",
+                dir,
+                std::io::Error::last_os_error()
+            );
+        }
+
+        wd
+    }
+
+    /// True if events are ready to read within `timeout_ms`, or forever if it is negative
+    fn poll(&self, timeout_ms: i32) -> bool {
+        let mut poll_fd = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        loop {
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout_ms) };
+            if ready >= 0 {
+                return ready > 0;
+            }
+
+            let err = std::io::Error::last_os_error();
+            if err.kind() != std::io::ErrorKind::Interrupted {
+                panic!("Failed to poll inotify: {}", err);
+            }
+        }
+    }
+
+    fn read_events(&self, events: &mut Vec<FileEvent>) {
+        const HEADER_SIZE: usize = std::mem::size_of::<libc::inotify_event>();
+
+        let mut buffer = [0u8; 4096];
+        let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        if read < 0 {
+            panic!(
+                "Failed to read inotify events: {}",
+                std::io::Error::last_os_error()
+            );
+        }
+
+        // each event is a header followed by a NUL padded file name of `len` bytes
+        let mut offset = 0;
+        while offset + HEADER_SIZE <= read as usize {
+            let header: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(buffer[offset..].as_ptr().cast()) };
+            let name_bytes =
+                &buffer[offset + HEADER_SIZE..offset + HEADER_SIZE + header.len as usize];
+            let name_len = name_bytes
+                .iter()
+                .position(|&b| b == 0)
+                .unwrap_or(name_bytes.len());
+
+            events.push(FileEvent {
+                wd: header.wd,
+                name: String::from_utf8_lossy(&name_bytes[..name_len]).to_string(),
+            });
+            offset += HEADER_SIZE + header.len as usize;
+        }
+    }
+
+    /// Blocks until a file changes, then collects changes until none arrive for `SETTLE_MS`
+    pub fn wait(&self) -> Vec<FileEvent> {
+        let mut events = vec![];
+        self.poll(-1);
+        while self.poll(SETTLE_MS) {
+            self.read_events(&mut events);
+        }
+
+        events
+    }
+}
+
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+/// Runs a day, then runs it again whenever one of its inputs changes. A change to the source
+/// rebuilds the binary and restarts it in its place.
+pub fn watch(runner: &SolutionRunner, day: u8) {
+    // resolved up front, as cargo replacing the binary leaves the old path marked deleted
+    let exe = std::env::current_exe().expect("Failed to find the running binary");
+
+    let source_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+    let inotify = Inotify::new();
+    let input_dir = runner
+        .input_dir(day)
+        .unwrap_or_else(|| panic!("Watch mode needs the input of day {} in a file", day));
+    let input_wd = inotify.watch_dir(&input_dir);
+    let input_file = runner.input_file_name();
+    let source_wds = [
+        inotify.watch_dir(source_dir),
+        inotify.watch_dir(&format!("{}/solutions", source_dir)),
+    ];
+
+    let mut previous: Option<Vec<String>> = std::env::var(PREVIOUS_ANSWERS_VAR)
+        .ok()
+        .map(|answers| answers.lines().map(String::from).collect());
+    loop {
+        if let Some(answers) = run_and_diff(runner, day, previous.as_deref()) {
+            previous = Some(answers);
+        }
+
+        println!("Watching for changes to day {}...", day);
+        loop {
+            let events = inotify.wait();
+            let source_changed = events
+                .iter()
+                .any(|event| source_wds.contains(&event.wd) && event.name.ends_with(".rs"));
+            let input_changed = events.iter().any(|event| {
+                event.wd == input_wd
+                    && match &input_file {
+                        Some(name) => event.name == *name,
+                        None => event.name.ends_with(".txt"),
+                    }
+            });
+
+            if source_changed && runner.uses_plugins() {
+                println!("Source changed, rebuilding the day {} plugin", day);
+                if rebuild_plugin(day) {
+                    break;
+                }
+                println!("Build failed, still watching with the previous plugin");
+            } else if source_changed {
+                println!("Source changed, rebuilding");
+                if rebuild() {
+                    let mut restart = Command::new(&exe);
+                    restart.args(std::env::args().skip(1));
+                    if let Some(answers) = &previous {
+                        restart.env(PREVIOUS_ANSWERS_VAR, answers.join("\n"));
+                    }
+
+                    let err = restart.exec();
+                    panic!("Failed to restart {}: {}", exe.display(), err);
+                }
+                println!("Build failed, still watching with the previous build");
+            } else if input_changed {
+                break;
+            }
+        }
+    }
+}
+
+/// Runs the day and prints which lines changed since `previous`. Returns the new answers, or
+/// `None` if the day panicked.
+fn run_and_diff(
+    runner: &SolutionRunner,
+    day: u8,
+    previous: Option<&[String]>,
+) -> Option<Vec<String>> {
+    let answers = match catch_unwind(AssertUnwindSafe(|| runner.run_and_print(day).lines)) {
+        Ok(answers) => answers,
+        Err(_) => {
+            println!("Day {} failed, keeping the previous answers", day);
+            return None;
+        }
+    };
+
+    let Some(previous) = previous else {
+        return Some(answers);
+    };
+
+    let mut changes = vec![];
+    for i in 0..answers.len().max(previous.len()) {
+        let (old, new) = (previous.get(i), answers.get(i));
+        if old != new {
+            changes.extend(old.map(|line| format!("- {}", line.trim())));
+            changes.extend(new.map(|line| format!("+ {}", line.trim())));
+        }
+    }
+
+    if changes.is_empty() {
+        println!("No changes since the last run");
+    } else {
+        println!("Changes since the last run:");
+        changes.iter().for_each(|change| println!("\t{}", change));
+    }
+
+    Some(answers)
+}
+
+/// Builds the binary with the same profile and features as the running one
+fn rebuild() -> bool {
+    let mut cargo = Command::new("cargo");
+    cargo.arg("build").current_dir(env!("CARGO_MANIFEST_DIR"));
+    if !cfg!(debug_assertions) {
+        cargo.arg("--release");
+    }
+    if cfg!(feature = "bigint") {
+        cargo.args(["--features", "bigint"]);
+    }
+
+    run_build(cargo)
+}
+
+/// Builds the day's plugin, which the next run loads in place of the old one
+fn rebuild_plugin(day: u8) -> bool {
+    let mut build = Command::new(concat!(env!("CARGO_MANIFEST_DIR"), "/build_plugin.sh"));
+    build.arg(day.to_string());
+
+    run_build(build)
+}
+
+fn run_build(mut build: Command) -> bool {
+    match build.status() {
+        Ok(status) => status.success(),
+        Err(err) => {
+            println!("Failed to run {:?}: {}", build.get_program(), err);
+            false
+        }
+    }
+}